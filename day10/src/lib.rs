@@ -1,144 +1,331 @@
-use std::cell::RefCell;
-use std::collections::{HashSet, VecDeque};
-use std::hash::{Hash, Hasher};
-use std::rc::Rc;
-
-type NodeRef = Rc<RefCell<Node>>;
-
-#[derive(Debug)]
-struct Node {
-    id: u32,
-    num: u8,
-    forward: Vec<NodeRef>,
-    reaches_9: u32,
+use std::cmp::Reverse;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Pos {
+    pub x: usize,
+    pub y: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseError;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum Adjacency {
+    /// Up, down, left and right.
+    #[default]
+    Orthogonal,
+    /// Diagonal neighbours as well.
+    All,
+}
+
+/// Which steps a trail may take.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StepRule {
+    climb: RangeInclusive<i8>,
+    adjacency: Adjacency,
+    impassable: Vec<char>,
 }
 
-impl Hash for Node {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.id.hash(state)
+impl Default for StepRule {
+    fn default() -> Self {
+        StepRule {
+            climb: 1..=1,
+            adjacency: Adjacency::Orthogonal,
+            impassable: vec!['.'],
+        }
     }
 }
 
-fn dfs(root: NodeRef, target: u8) -> u32 {
-    let mut queue = VecDeque::new();
-    queue.push_back(root);
+impl StepRule {
+    /// Allowed height change per step, e.g. `1..=2`, or `0..=9` for any
+    /// non-decreasing step.
+    pub fn with_climb(mut self, climb: RangeInclusive<i8>) -> Self {
+        self.climb = climb;
+        self
+    }
+
+    pub fn with_adjacency(mut self, adjacency: Adjacency) -> Self {
+        self.adjacency = adjacency;
+        self
+    }
+
+    /// Characters that mark cells no trail can enter.
+    pub fn with_impassable(mut self, markers: &[char]) -> Self {
+        self.impassable = markers.to_vec();
+        self
+    }
+
+    fn allows(&self, from: u8, to: u8) -> bool {
+        self.climb.contains(&(to as i8 - from as i8))
+    }
+
+    // every step goes strictly up, so trails can never loop
+    fn is_acyclic(&self) -> bool {
+        *self.climb.start() > 0
+    }
+}
+
+/// A topographic map, stored as a graph where every cell links to the
+/// neighbours its [`StepRule`] allows stepping to.
+#[derive(Debug, Clone)]
+pub struct TrailMap {
+    width: usize,
+    // `None` for impassable cells
+    heights: Vec<Option<u8>>,
+    next: Vec<Vec<usize>>,
+    rule: StepRule,
+    start: u8,
+    end: u8,
+}
 
-    let mut result = HashSet::new();
-    while let Some(node) = queue.pop_front() {
-        // Check if this is the node we are looking for
-        if node.borrow().num == target {
-            result.insert(node.borrow().id);
-            continue;
+impl FromStr for TrailMap {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TrailMap::new(s, StepRule::default())
+    }
+}
+
+impl TrailMap {
+    pub fn new(s: &str, rule: StepRule) -> Result<Self, ParseError> {
+        let width = s.lines().next().ok_or(ParseError)?.chars().count();
+        let mut heights = Vec::new();
+        for line in s.lines() {
+            let len = heights.len();
+            for c in line.chars() {
+                if rule.impassable.contains(&c) {
+                    heights.push(None);
+                } else {
+                    heights.push(Some(c.to_digit(10).ok_or(ParseError)? as u8));
+                }
+            }
+            if heights.len() - len != width {
+                return Err(ParseError);
+            }
+        }
+
+        let mut map = TrailMap {
+            width,
+            heights,
+            next: vec![],
+            rule,
+            start: 0,
+            end: 9,
+        };
+        map.next = (0..map.heights.len()).map(|i| map.steps(i)).collect();
+        Ok(map)
+    }
+
+    /// Trails run from `start` up to `end` instead of from 0 to 9.
+    pub fn with_heights(mut self, start: u8, end: u8) -> Self {
+        self.start = start;
+        self.end = end;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.heights.len() / self.width
+    }
+
+    /// `None` outside the map and on impassable cells.
+    pub fn height_at(&self, pos: Pos) -> Option<u8> {
+        if pos.x >= self.width {
+            return None;
         }
+        self.heights
+            .get(pos.y * self.width + pos.x)
+            .copied()
+            .flatten()
+    }
 
-        // Add left and write to the back of the queue for DFS
-        for forward in &node.borrow().forward {
-            queue.push_front(forward.clone());
+    fn pos(&self, i: usize) -> Pos {
+        Pos {
+            x: i % self.width,
+            y: i / self.width,
         }
     }
 
-    // Search completed and node was not found
-    result.len() as u32
-}
+    // the cell index of `pos`, if a trail can start there
+    fn head(&self, pos: Pos) -> Option<usize> {
+        (self.height_at(pos) == Some(self.start)).then(|| pos.y * self.width + pos.x)
+    }
 
-fn build_nodes(input: &str) -> Vec<NodeRef> {
-    let mut nodes: Vec<_> = input
-        .lines()
-        .flat_map(|line| line.as_bytes().into_iter().map(|x| x - b'0'))
-        .enumerate()
-        .map(|(i, num)| Node {
-            id: i as u32,
-            num: num as u8,
-            forward: Vec::new(),
-            reaches_9: 0,
-        })
-        .map(|node| Rc::new(RefCell::new(node)))
-        .collect();
-    let width = input.lines().next().unwrap().len();
-    let mut sorted_nodes = nodes.clone();
-
-    sorted_nodes.sort_unstable_by_key(|node| node.borrow().num);
-
-    for node in sorted_nodes.iter().rev() {
-        let mut neighbour_indices = Vec::new();
-        let idx = node.borrow().id as usize;
-
-        if idx % width != 0 {
-            neighbour_indices.push(idx - 1);
-        } // left
-        if idx >= width {
-            neighbour_indices.push(idx - width);
-        } // up
-        if (idx + 1) % width != 0 && idx + 1 < nodes.len() {
-            neighbour_indices.push(idx + 1);
-        } // right
-        if idx + width < nodes.len() {
-            neighbour_indices.push(idx + width);
-        } // down
-
-        let neighbours = neighbour_indices
-            .iter()
-            .map(|idx| &nodes[*idx])
-            .filter(|n| n.borrow().num == node.borrow().num + 1)
-            .map(|n| n.clone())
-            .collect::<Vec<_>>();
-
-        node.borrow_mut().forward = neighbours;
-        if node.borrow().num == 9 {
-            node.borrow_mut().reaches_9 = 1;
-            continue;
+    fn is_summit(&self, i: usize) -> bool {
+        self.heights[i] == Some(self.end)
+    }
+
+    fn steps(&self, i: usize) -> Vec<usize> {
+        let Some(from) = self.heights[i] else {
+            return vec![];
+        };
+
+        let (x, y) = ((i % self.width) as isize, (i / self.width) as isize);
+        let mut offsets = vec![(-1, 0), (0, -1), (1, 0), (0, 1)];
+        if self.rule.adjacency == Adjacency::All {
+            offsets.extend([(-1, -1), (1, -1), (1, 1), (-1, 1)]);
+        }
+
+        offsets
+            .into_iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .filter(|&(x, y)| {
+                (0..self.width as isize).contains(&x) && (0..self.height() as isize).contains(&y)
+            })
+            .map(|(x, y)| y as usize * self.width + x as usize)
+            .filter(|&n| self.heights[n].is_some_and(|to| self.rule.allows(from, to)))
+            .collect()
+    }
+
+    /// Every cell at the start height, in reading order.
+    pub fn trailheads(&self) -> Vec<Pos> {
+        (0..self.heights.len())
+            .filter(|&i| self.heights[i] == Some(self.start))
+            .map(|i| self.pos(i))
+            .collect()
+    }
+
+    /// The number of distinct summits reachable from `head`.
+    pub fn score(&self, head: Pos) -> usize {
+        let Some(head) = self.head(head) else {
+            return 0;
+        };
+
+        let mut seen = vec![false; self.heights.len()];
+        let mut stack = vec![head];
+        let mut summits = 0;
+        seen[head] = true;
+        while let Some(i) = stack.pop() {
+            if self.is_summit(i) {
+                summits += 1;
+                continue;
+            }
+            for &n in &self.next[i] {
+                if !seen[n] {
+                    seen[n] = true;
+                    stack.push(n);
+                }
+            }
+        }
+        summits
+    }
+
+    /// The number of distinct trails from `head` to any summit.
+    ///
+    /// When the rule allows flat or downhill steps, trails never visit a
+    /// cell twice, and counting them means walking every one of them.
+    pub fn rating(&self, head: Pos) -> u64 {
+        match self.head(head) {
+            Some(_) if !self.rule.is_acyclic() => self.trails(head).count() as u64,
+            Some(i) => self.ratings()[i],
+            None => 0,
+        }
+    }
+
+    // trails from every cell to a summit, filled from the top down
+    fn ratings(&self) -> Vec<u64> {
+        let mut order: Vec<usize> = (0..self.heights.len()).collect();
+        order.sort_unstable_by_key(|&i| Reverse(self.heights[i]));
+
+        let mut ratings = vec![0; self.heights.len()];
+        for i in order {
+            ratings[i] = if self.is_summit(i) {
+                1
+            } else {
+                self.next[i].iter().map(|&n| ratings[n]).sum()
+            };
+        }
+        ratings
+    }
+
+    pub fn total_score(&self) -> usize {
+        self.trailheads().into_iter().map(|h| self.score(h)).sum()
+    }
+
+    pub fn total_rating(&self) -> u64 {
+        if !self.rule.is_acyclic() {
+            return self.trailheads().into_iter().map(|h| self.rating(h)).sum();
         }
 
-        let sum = node
-            .borrow()
-            .forward
-            .iter()
-            .map(|n| n.borrow().reaches_9)
-            .sum();
-        node.borrow_mut().reaches_9 = sum;
+        let ratings = self.ratings();
+        self.trailheads()
+            .into_iter()
+            .filter_map(|h| self.head(h))
+            .map(|i| ratings[i])
+            .sum()
     }
 
-    return nodes;
+    /// Every trail from `head` to a summit, as the positions along it.
+    pub fn trails(&self, head: Pos) -> Trails<'_> {
+        let mut on_trail = vec![false; self.heights.len()];
+        let stack = match self.head(head) {
+            Some(i) => {
+                on_trail[i] = true;
+                vec![(i, 0)]
+            }
+            None => vec![],
+        };
+        Trails {
+            map: self,
+            stack,
+            on_trail,
+        }
+    }
 }
 
-pub fn part1(input: &str) -> u32 {
-    let nodes = build_nodes(input);
+/// Depth-first walk over the trails from one trailhead.
+pub struct Trails<'a> {
+    map: &'a TrailMap,
+    // cell and the index of the next step to follow
+    stack: Vec<(usize, usize)>,
+    on_trail: Vec<bool>,
+}
+
+impl Trails<'_> {
+    fn pop(&mut self) {
+        if let Some((i, _)) = self.stack.pop() {
+            self.on_trail[i] = false;
+        }
+    }
+}
+
+impl Iterator for Trails<'_> {
+    type Item = Vec<Pos>;
 
-    nodes
-        .into_iter()
-        .filter(|node| node.borrow().num == 0)
-        .map(|trailstart| dfs(trailstart.clone(), 9))
-        .sum::<u32>()
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&mut (i, ref mut step)) = self.stack.last_mut() {
+            if *step == 0 && self.map.is_summit(i) {
+                let trail = self.stack.iter().map(|&(i, _)| self.map.pos(i)).collect();
+                self.pop();
+                return Some(trail);
+            }
+
+            match self.map.next[i].get(*step) {
+                Some(&n) => {
+                    *step += 1;
+                    if !self.on_trail[n] {
+                        self.on_trail[n] = true;
+                        self.stack.push((n, 0));
+                    }
+                }
+                None => self.pop(),
+            }
+        }
+        None
+    }
+}
+
+pub fn part1(input: &str) -> u32 {
+    let map = input.parse::<TrailMap>().expect("could not parse map");
+    map.total_score() as u32
 }
 
 pub fn part2(input: &str) -> u32 {
-    let nodes = build_nodes(input);
-
-    // for n in nodes.iter().filter(|n| n.borrow().num == 9) {
-    //     n.borrow_mut().reaches_9 = 1;
-    // }
-
-    // // build op a cache
-    // for i in (0..=8).rev() {
-    //     for node in nodes.iter().filter(|n| n.borrow().num == i) {
-    //         let sum = node
-    //             .borrow()
-    //             .forward
-    //             .iter()
-    //             .map(|n| n.borrow().reaches_9)
-    //             .sum();
-    //         node.borrow_mut().reaches_9 = sum;
-    //     }
-    // }
-
-    nodes
-        .iter()
-        .filter(|n| n.borrow().num == 0)
-        .map(|n| n.borrow().reaches_9)
-        .sum()
-    // nodes
-    //     .into_iter()
-    //     .filter(|node| node.borrow().num == 0)
-    //     .map(|trailstart| dfs_distinct(trailstart.clone(), 9))
-    //     .sum::<u32>()
+    let map = input.parse::<TrailMap>().expect("could not parse map");
+    map.total_rating() as u32
 }
@@ -1,4 +1,6 @@
 use day10::{part1, part2};
+#[cfg(test)]
+use day10::{Adjacency, Pos, StepRule, TrailMap};
 use std::fs;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -22,13 +24,13 @@ fn test_part_1() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part1(&contents);
     assert_eq!(num, 36);
 
-    return Ok(());
+    Ok(())
 }
 
 #[test]
@@ -36,11 +38,84 @@ fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part2(&contents);
     assert_eq!(num, 81);
 
-    return Ok(());
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[test]
+fn test_trails() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "input/dummy.txt";
+
+    // Read file contents
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+
+    let map: TrailMap = contents.parse().map_err(|_| "invalid map")?;
+    let heads = map.trailheads();
+    assert_eq!(heads.len(), 9);
+    assert_eq!(heads[0], Pos { x: 2, y: 0 });
+    assert_eq!(map.score(heads[0]), 5);
+    assert_eq!(map.rating(heads[0]), 20);
+
+    let trails: Vec<_> = map.trails(heads[0]).collect();
+    assert_eq!(trails.len(), 20);
+    for trail in &trails {
+        let heights: Vec<_> = trail.iter().filter_map(|&p| map.height_at(p)).collect();
+        assert_eq!(heights, (0..=9).collect::<Vec<_>>());
+    }
+
+    // from the 8s at the top left corner, one step up to a 9
+    let map = map.with_heights(8, 9);
+    assert_eq!(map.score(Pos { x: 1, y: 1 }), 1);
+    assert_eq!(
+        map.trails(Pos { x: 0, y: 0 }).collect::<Vec<_>>(),
+        vec![vec![Pos { x: 0, y: 0 }, Pos { x: 1, y: 0 }]]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_step_rules() -> Result<(), Box<dyn std::error::Error>> {
+    // `.` is impassable by default
+    let map: TrailMap = "..90..9\n...1.98\n...2..7\n6543456\n765.987\n876....\n987...."
+        .parse()
+        .map_err(|_| "invalid map")?;
+    assert_eq!(map.total_score(), 4);
+
+    let map: TrailMap = ".....0.\n..4321.\n..5..2.\n..6543.\n..7..4.\n..8765.\n..9...."
+        .parse()
+        .map_err(|_| "invalid map")?;
+    assert_eq!(map.total_rating(), 3);
+
+    let head = Pos { x: 0, y: 0 };
+    let rule = StepRule::default().with_climb(1..=2);
+    let map = TrailMap::new("02\n13", rule).map_err(|_| "invalid map")?;
+    let map = map.with_heights(0, 3);
+    assert_eq!(map.score(head), 1);
+    assert_eq!(map.rating(head), 2);
+
+    let map = TrailMap::new("0#\n#1", StepRule::default().with_impassable(&['#']))
+        .map_err(|_| "invalid map")?
+        .with_heights(0, 1);
+    assert_eq!(map.score(head), 0);
+    let rule = StepRule::default()
+        .with_impassable(&['#'])
+        .with_adjacency(Adjacency::All);
+    let map = TrailMap::new("0#\n#1", rule).map_err(|_| "invalid map")?;
+    assert_eq!(map.with_heights(0, 1).score(head), 1);
+
+    // flat steps allowed, so trails may wander over the 0s
+    let rule = StepRule::default().with_climb(0..=9);
+    let map = TrailMap::new("00\n09", rule).map_err(|_| "invalid map")?;
+    assert_eq!(map.score(head), 1);
+    assert_eq!(map.rating(head), 2);
+    assert_eq!(map.total_rating(), 6);
+
+    Ok(())
+}
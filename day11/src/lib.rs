@@ -1,141 +1,366 @@
-use std::cell::RefCell;
+use num_bigint::BigUint;
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::fmt;
+use std::num::ParseIntError;
 
-fn split_num(num: u64) -> Vec<u64> {
+/// The number engraved on a stone.
+pub type Stone = u128;
+
+/// What a single stone turns into when you blink.
+pub trait StoneRules {
+    fn blink(&self, stone: Stone) -> Vec<Stone>;
+}
+
+/// The puzzle's rules: 0 becomes 1, an even number of digits splits in
+/// two halves, and anything else is multiplied by 2024.
+///
+/// A product has an even number of digits at the latest after the second
+/// multiplication in a row, so stones parsed from a `u64` never grow past
+/// 26 digits. Panics on stones too large for a `Stone` to hold the product.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Plutonian;
+
+fn split_num(num: Stone) -> Vec<Stone> {
     let digits = num_digits(num);
 
-    let left = num / 10u64.pow(digits / 2);
-    let right = num - (left * 10u64.pow(digits / 2));
+    let left = num / 10u128.pow(digits / 2);
+    let right = num - (left * 10u128.pow(digits / 2));
 
     vec![left, right]
 }
 
-fn num_digits(num: u64) -> u32 {
-    u64::ilog10(num) + 1
+fn num_digits(num: Stone) -> u32 {
+    u128::ilog10(num) + 1
 }
 
-fn change_stone(num: u64) -> Vec<u64> {
-    if num == 0 {
-        return vec![1];
+impl StoneRules for Plutonian {
+    fn blink(&self, num: Stone) -> Vec<Stone> {
+        if num == 0 {
+            return vec![1];
+        }
+        if num_digits(num).is_multiple_of(2) {
+            return split_num(num);
+        }
+        vec![num.checked_mul(2024).expect("stone overflowed u128")]
     }
-    if num_digits(num) % 2 == 0 {
-        return split_num(num);
+}
+
+pub fn parse_stones(input: &str) -> Result<Vec<Stone>, ParseIntError> {
+    input
+        .split_ascii_whitespace()
+        .map(|s| s.parse::<u64>().map(Stone::from))
+        .collect()
+}
+
+/// How stone counts are added up and multiplied.
+pub trait Arithmetic {
+    type Value: Clone + PartialEq + fmt::Debug;
+
+    fn value(&self, n: u64) -> Self::Value;
+    fn add(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+    fn mul(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+
+    fn zero(&self) -> Self::Value {
+        self.value(0)
     }
-    vec![num * 2024]
 }
 
-type NodeRef = Rc<RefCell<Node>>;
+/// Plain `u64` counts, panicking instead of wrapping on overflow.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct U64;
+
+impl Arithmetic for U64 {
+    type Value = u64;
+
+    fn value(&self, n: u64) -> u64 {
+        n
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        a.checked_add(*b).expect("stone count overflowed u64")
+    }
 
-#[derive(Debug)]
-struct Node {
-    num: u64,
-    children: Vec<NodeRef>,
+    fn mul(&self, a: &u64, b: &u64) -> u64 {
+        a.checked_mul(*b).expect("stone count overflowed u64")
+    }
 }
 
-fn fill_cache(num: u64, cache: &mut HashMap<u64, NodeRef>) {
-    // Check if the node already exists in the cache
-    let node = match cache.get(&num) {
-        Some(existing) => existing.clone(),
-        None => {
-            // If not: create a new empty node
-            let new_node = Rc::new(RefCell::new(Node {
-                num,
-                children: Vec::new(),
-            }));
-            cache.insert(num, new_node.clone());
-            new_node
-        }
-    };
-
-    // If the node already has children, nothing to do.
-    if !node.borrow().children.is_empty() {
-        return;
-    }
-
-    // We have found an empty node, let's create some children
-    let mut children = Vec::new();
-    for child_num in change_stone(num) {
-        // Since we're not currently holding a reference borrowed from get,
-        // we can safely mutate the cache while we do this.
-        let child_node = match cache.get(&child_num) {
-            Some(child) => child.clone(),
-            None => {
-                let child_node = Rc::new(RefCell::new(Node {
-                    num: child_num,
-                    children: Vec::new(),
-                }));
-                cache.insert(child_num, child_node.clone());
-                child_node
-            }
-        };
-        children.push(child_node);
+/// Arbitrary-precision counts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Exact;
+
+impl Arithmetic for Exact {
+    type Value = BigUint;
+
+    fn value(&self, n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a + b
     }
 
-    // Now safely update the current node.
-    node.borrow_mut().children = children;
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b
+    }
+}
+
+/// Counts modulo a (typically prime) modulus.
+#[derive(Debug, Clone, Copy)]
+pub struct Modulo(u64);
+
+impl Modulo {
+    /// Returns `None` for a zero modulus.
+    pub fn new(modulus: u64) -> Option<Modulo> {
+        (modulus > 0).then_some(Modulo(modulus))
+    }
 }
 
-fn count(node: NodeRef, c: usize, cache: &mut HashMap<(u64, usize), u64>) -> u64 {
-    // base case: we are the last node
-    if c == 0 {
-        return 1;
+impl Arithmetic for Modulo {
+    type Value = u64;
+
+    fn value(&self, n: u64) -> u64 {
+        n % self.0
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 + *b as u128) % self.0 as u128) as u64
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 * *b as u128) % self.0 as u128) as u64
+    }
+}
+
+/// Evolves the stones as a multiset, since the order of the line never
+/// matters for how many there are.
+#[derive(Debug, Clone)]
+pub struct Simulation<R: StoneRules, A: Arithmetic = U64> {
+    rules: R,
+    arithmetic: A,
+    // stone value -> how many stones carry it
+    stones: HashMap<Stone, A::Value>,
+    // stones each value turns into, remembered across blinks
+    cache: HashMap<Stone, Vec<Stone>>,
+}
+
+impl<R: StoneRules> Simulation<R> {
+    pub fn new(rules: R, stones: &[Stone]) -> Self {
+        Simulation::with_arithmetic(rules, U64, stones)
+    }
+}
+
+impl<R: StoneRules, A: Arithmetic> Simulation<R, A> {
+    pub fn with_arithmetic(rules: R, arithmetic: A, stones: &[Stone]) -> Self {
+        let mut counts = HashMap::new();
+        for &stone in stones {
+            let count = counts.entry(stone).or_insert_with(|| arithmetic.zero());
+            *count = arithmetic.add(count, &arithmetic.value(1));
+        }
+
+        Simulation {
+            rules,
+            arithmetic,
+            stones: counts,
+            cache: HashMap::new(),
+        }
     }
 
-    let node = node.borrow();
-    let mut result = 0;
+    pub fn stones(&self) -> &HashMap<Stone, A::Value> {
+        &self.stones
+    }
 
-    // Ask each child if they have already computed the number of stones generated
-    // when playing c - 1 times
-    for child in node.children.clone() {
-        let num = child.borrow().num;
+    pub fn count(&self) -> A::Value {
+        self.stones.values().fold(self.arithmetic.zero(), |sum, n| {
+            self.arithmetic.add(&sum, n)
+        })
+    }
 
-        let count = match cache.get(&(num, c - 1)) {
-            // If they do, great! we don't have to do the entire computation
-            Some(count) => *count,
-            // If they don't, then we'll need to ask the children of that node
-            None => {
-                // We'll do that recusively.. so try to get warp your head around it
-                let count = count(child.clone(), c - 1, cache);
-                // And cache the result to prevent future lookups
-                cache.insert((num, c - 1), count);
-                count
+    pub fn blink(&mut self) {
+        let mut next: HashMap<Stone, A::Value> = HashMap::with_capacity(self.stones.len());
+        for (&stone, n) in &self.stones {
+            let children = self
+                .cache
+                .entry(stone)
+                .or_insert_with(|| self.rules.blink(stone));
+            for &child in children.iter() {
+                let count = next.entry(child).or_insert_with(|| self.arithmetic.zero());
+                *count = self.arithmetic.add(count, n);
             }
-        };
+        }
+        self.stones = next;
+    }
 
-        // Add the total number of stones generated by the children to the result
-        result += count;
+    pub fn blink_times(&mut self, blinks: u64) -> A::Value {
+        for _ in 0..blinks {
+            self.blink();
+        }
+        self.count()
     }
 
-    result
+    /// Blinks forever, yielding the number of stones after each blink.
+    pub fn history(&mut self) -> impl Iterator<Item = A::Value> + '_ {
+        std::iter::from_fn(move || {
+            self.blink();
+            Some(self.count())
+        })
+    }
 }
 
-pub fn partx(input: &str, target: usize) -> u64 {
-    let nums = input
-        .split_ascii_whitespace()
-        .map(|num| num.parse::<u64>().unwrap())
-        .collect::<Vec<_>>();
+/// Every stone value reachable from a starting line, with the values each
+/// one turns into. Blinking never leaves this set, so it is enough to
+/// describe the simulation as a matrix.
+#[derive(Debug, Clone)]
+pub struct Closure {
+    values: Vec<Stone>,
+    index: HashMap<Stone, usize>,
+    next: Vec<Vec<usize>>,
+}
+
+impl Closure {
+    /// Returns `None` if more than `limit` distinct values turn up.
+    pub fn find<R: StoneRules>(rules: &R, stones: &[Stone], limit: usize) -> Option<Closure> {
+        let mut closure = Closure {
+            values: vec![],
+            index: HashMap::new(),
+            next: vec![],
+        };
+        for &stone in stones {
+            closure.insert(stone);
+        }
+
+        let mut i = 0;
+        while i < closure.values.len() {
+            if closure.values.len() > limit {
+                return None;
+            }
+            let next = rules
+                .blink(closure.values[i])
+                .into_iter()
+                .map(|child| closure.insert(child))
+                .collect();
+            closure.next.push(next);
+            i += 1;
+        }
+
+        Some(closure)
+    }
+
+    fn insert(&mut self, value: Stone) -> usize {
+        *self.index.entry(value).or_insert_with(|| {
+            self.values.push(value);
+            self.values.len() - 1
+        })
+    }
+
+    pub fn values(&self) -> &[Stone] {
+        &self.values
+    }
 
-    let mut lookup: HashMap<u64, NodeRef> = HashMap::new();
-    let mut count_lookup: HashMap<(u64, usize), u64> = HashMap::new();
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
 
-    let mut result = 0;
+    /// The number of stones after `blinks` blinks, by raising the
+    /// transition matrix to that power. Each squaring is cubic in the size
+    /// of the closure. Returns `None` if a stone is not in the closure.
+    pub fn count<A: Arithmetic>(
+        &self,
+        arithmetic: &A,
+        stones: &[Stone],
+        blinks: u64,
+    ) -> Option<A::Value> {
+        let n = self.len();
+        let one = arithmetic.value(1);
 
-    for num in nums.into_iter() {
-        fill_cache(num, &mut lookup);
+        // m[i][j]: stones of value j that one stone of value i turns into
+        let mut m = vec![vec![arithmetic.zero(); n]; n];
+        for (i, next) in self.next.iter().enumerate() {
+            for &j in next {
+                m[i][j] = arithmetic.add(&m[i][j], &one);
+            }
+        }
 
-        for _ in 0..target {
-            let keys: Vec<_> = lookup.keys().cloned().collect();
-            for key in keys {
-                fill_cache(key, &mut lookup);
+        // stones per value, multiplied by the powers of m picked from the
+        // bits of `blinks`
+        let mut v = vec![arithmetic.zero(); n];
+        for stone in stones {
+            let &i = self.index.get(stone)?;
+            v[i] = arithmetic.add(&v[i], &one);
+        }
+        let mut blinks = blinks;
+        while blinks > 0 {
+            if blinks & 1 == 1 {
+                v = vec_mul(arithmetic, &v, &m);
+            }
+            blinks >>= 1;
+            if blinks > 0 {
+                m = mat_mul(arithmetic, &m, &m);
             }
         }
 
-        let node = lookup.get(&num).unwrap();
-        result += count(node.clone(), target, &mut count_lookup);
+        Some(
+            v.iter()
+                .fold(arithmetic.zero(), |sum, c| arithmetic.add(&sum, c)),
+        )
     }
+}
 
-    result
+fn vec_mul<A: Arithmetic>(arithmetic: &A, v: &[A::Value], m: &[Vec<A::Value>]) -> Vec<A::Value> {
+    let zero = arithmetic.zero();
+    let mut out = vec![zero.clone(); m.len()];
+    for (i, a) in v.iter().enumerate() {
+        if *a == zero {
+            continue;
+        }
+        for (j, b) in m[i].iter().enumerate() {
+            if *b != zero {
+                out[j] = arithmetic.add(&out[j], &arithmetic.mul(a, b));
+            }
+        }
+    }
+    out
+}
+
+fn mat_mul<A: Arithmetic>(
+    arithmetic: &A,
+    a: &[Vec<A::Value>],
+    b: &[Vec<A::Value>],
+) -> Vec<Vec<A::Value>> {
+    a.iter().map(|row| vec_mul(arithmetic, row, b)).collect()
+}
+
+/// The number of stones after `blinks` blinks, for any number of blinks.
+///
+/// Uses the matrix over the [`Closure`] when one of at most `limit` values
+/// exists and squaring it is cheaper than blinking one step at a time.
+pub fn count_after<R: StoneRules, A: Arithmetic>(
+    rules: R,
+    arithmetic: A,
+    stones: &[Stone],
+    blinks: u64,
+    limit: usize,
+) -> A::Value {
+    if let Some(closure) = Closure::find(&rules, stones, limit) {
+        let n = closure.len() as u64;
+        let squarings = 64 - blinks.leading_zeros() as u64;
+        if n.saturating_mul(n).saturating_mul(squarings) < blinks {
+            if let Some(count) = closure.count(&arithmetic, stones, blinks) {
+                return count;
+            }
+        }
+    }
+
+    Simulation::with_arithmetic(rules, arithmetic, stones).blink_times(blinks)
+}
+
+pub fn partx(input: &str, target: usize) -> u64 {
+    let stones = parse_stones(input).expect("could not parse stones");
+    Simulation::new(Plutonian, &stones).blink_times(target as u64)
 }
 
 pub fn part1(input: &str) -> u64 {
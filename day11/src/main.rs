@@ -1,4 +1,8 @@
 use day11::{part1, part2};
+#[cfg(test)]
+use day11::{
+    count_after, parse_stones, Closure, Exact, Modulo, Plutonian, Simulation, Stone, StoneRules,
+};
 use std::fs;
 
 
@@ -23,13 +27,13 @@ fn test_part_1() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part1(&contents);
     assert_eq!(num, 55312);
 
-    return Ok(());
+    Ok(())
 }
 
 #[test]
@@ -37,11 +41,89 @@ fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part2(&contents);
     assert_eq!(num, 65601038650482);
 
-    return Ok(());
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[test]
+fn test_simulation() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "input/dummy.txt";
+
+    // Read file contents
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+
+    let stones = parse_stones(&contents)?;
+    let mut simulation = Simulation::new(Plutonian, &stones);
+    let history: Vec<_> = simulation.history().take(6).collect();
+    assert_eq!(history, vec![3, 4, 5, 9, 13, 22]);
+    assert_eq!(simulation.stones().get(&2), Some(&4));
+
+    // every stone doubles and keeps its value
+    struct Twins;
+    impl StoneRules for Twins {
+        fn blink(&self, stone: Stone) -> Vec<Stone> {
+            vec![stone, stone]
+        }
+    }
+    let mut simulation = Simulation::new(Twins, &stones);
+    assert_eq!(simulation.blink_times(10), 2048);
+    assert_eq!(simulation.stones().len(), 2);
+
+    // 10^18 * 2024 no longer fits in a u64
+    let stones = parse_stones("1000000000000000000 18446744073709551615")?;
+    let mut simulation = Simulation::new(Plutonian, &stones);
+    assert_eq!(simulation.blink_times(1), 3);
+    assert_eq!(simulation.stones().get(&(2024 * 10u128.pow(18))), Some(&1));
+    assert_eq!(simulation.blink_times(1), 6);
+
+    Ok(())
+}
+
+#[test]
+fn test_large_counts() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "input/dummy.txt";
+
+    // Read file contents
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+
+    let stones = parse_stones(&contents)?;
+    let exact = Simulation::with_arithmetic(Plutonian, Exact, &stones).blink_times(75);
+    assert_eq!(exact, 65601038650482u64.into());
+
+    assert!(Modulo::new(0).is_none());
+    let p = 1_000_000_007;
+    let modulo = Modulo::new(p).ok_or("zero modulus")?;
+    let counted = Simulation::with_arithmetic(Plutonian, modulo, &stones).blink_times(75);
+    assert_eq!(counted, 65601038650482 % p);
+
+    assert!(Closure::find(&Plutonian, &stones, 10).is_none());
+    let closure = Closure::find(&Plutonian, &stones, 1000).ok_or("no closure")?;
+    assert_eq!(closure.len(), 76);
+    assert_eq!(closure.count(&Exact, &stones, 75), Some(exact));
+    assert!(!closure.values().contains(&125125));
+    assert_eq!(closure.count(&Exact, &[125125], 75), None);
+
+    // far past u64, by both routes
+    let stepped = Simulation::with_arithmetic(Plutonian, Exact, &stones).blink_times(300);
+    assert!(stepped > u64::MAX.into());
+    assert_eq!(closure.count(&Exact, &stones, 300), Some(stepped.clone()));
+    assert_eq!(
+        closure.count(&modulo, &stones, 300),
+        Some(u64::try_from(&stepped % p)?)
+    );
+
+    let huge = 1 << 40;
+    assert_eq!(
+        Some(count_after(Plutonian, modulo, &stones, huge, 1000)),
+        closure.count(&modulo, &stones, huge)
+    );
+
+    Ok(())
+}
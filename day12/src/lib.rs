@@ -1,291 +1,473 @@
+use geo::{Coord, LineString, Polygon};
 use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-struct Node {
-    x: i32,
-    y: i32,
-    id: char,
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct Pos {
+    pub x: usize,
+    pub y: usize,
 }
 
-impl Node {
-    fn get_neighbour_down(&self) -> Node {
-        Node {
-            x: self.x,
-            y: self.y + 1,
-            id: self.id,
-        }
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseError;
+
+/// Inclusive on both ends.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BoundingBox {
+    pub min: Pos,
+    pub max: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub plant: char,
+    /// In reading order.
+    pub cells: Vec<Pos>,
+    pub area: usize,
+    pub perimeter: usize,
+    /// Straight stretches of fence, equal to the number of corners.
+    pub sides: usize,
+    /// Pockets of other plants enclosed by the region. Pockets touching the
+    /// outside only at a corner are not holes.
+    pub holes: usize,
+    pub bounds: BoundingBox,
+}
+
+impl Region {
+    pub fn price(&self) -> usize {
+        self.area * self.perimeter
     }
 
-    fn get_neighbour_up(&self) -> Node {
-        Node {
-            x: self.x,
-            y: self.y - 1,
-            id: self.id,
-        }
+    pub fn bulk_price(&self) -> usize {
+        self.area * self.sides
     }
 
-    fn get_neighbour_left(&self) -> Node {
-        Node {
-            x: self.x - 1,
-            y: self.y,
-            id: self.id,
-        }
+    /// The outline of the region, with cell `(x, y)` covering the unit
+    /// square from `(x, y)` to `(x + 1, y + 1)`.
+    pub fn to_polygon(&self) -> Polygon<f64> {
+        let mut rings = trace_rings(&self.cells);
+
+        // every other ring lies inside the outer one
+        let outer = (0..rings.len())
+            .max_by_key(|&i| ring_area(&rings[i]).abs())
+            .expect("a region has at least one cell");
+        let exterior = rings.swap_remove(outer);
+
+        let to_line = |ring: Vec<(i64, i64)>| {
+            LineString::from(
+                ring.into_iter()
+                    .map(|(x, y)| Coord {
+                        x: x as f64,
+                        y: y as f64,
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        };
+        Polygon::new(to_line(exterior), rings.into_iter().map(to_line).collect())
     }
+}
+
+// twice the signed area of a closed ring
+fn ring_area(ring: &[(i64, i64)]) -> i64 {
+    ring.windows(2)
+        .map(|w| w[0].0 * w[1].1 - w[1].0 * w[0].1)
+        .sum()
+}
 
-    fn get_neighbour_right(&self) -> Node {
-        Node {
-            x: self.x + 1,
-            y: self.y,
-            id: self.id,
+// closed rings of fence around the cells, without collinear points
+fn trace_rings(cells: &[Pos]) -> Vec<Vec<(i64, i64)>> {
+    let inside: HashSet<(i64, i64)> = cells.iter().map(|p| (p.x as i64, p.y as i64)).collect();
+
+    // directed fence segments, going round each cell the same way
+    let mut edges = vec![];
+    for &(x, y) in &inside {
+        let sides = [
+            ((0, -1), (x + 1, y), (x, y)),
+            ((-1, 0), (x, y), (x, y + 1)),
+            ((0, 1), (x, y + 1), (x + 1, y + 1)),
+            ((1, 0), (x + 1, y + 1), (x + 1, y)),
+        ];
+        for ((dx, dy), from, to) in sides {
+            if !inside.contains(&(x + dx, y + dy)) {
+                edges.push((from, to, (x, y)));
+            }
         }
     }
+    edges.sort();
 
-    fn get_neighbours(&self) -> Vec<Node> {
-        vec![
-            self.get_neighbour_up(),
-            self.get_neighbour_down(),
-            self.get_neighbour_left(),
-            self.get_neighbour_right(),
-        ]
+    let mut outgoing: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, &(from, _, _)) in edges.iter().enumerate() {
+        outgoing.entry(from).or_default().push(i);
     }
+
+    let mut used = vec![false; edges.len()];
+    let mut rings = vec![];
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+
+        let mut ring = vec![edges[first].0];
+        let mut edge = first;
+        loop {
+            used[edge] = true;
+            let (_, to, cell) = edges[edge];
+            ring.push(to);
+
+            // where two cells only touch at a corner, stay with the same
+            // cell so the region does not join up through the corner
+            let options = &outgoing[&to];
+            let next = options
+                .iter()
+                .copied()
+                .filter(|&e| !used[e])
+                .min_by_key(|&e| edges[e].2 != cell);
+            match next {
+                Some(next) => edge = next,
+                None => break,
+            }
+        }
+        rings.push(simplify(ring));
+    }
+    rings
 }
 
-pub fn part1(input: &str) -> u32 {
-    let nodes = input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars().enumerate().map(move |(x, char)| Node {
-                x: x as i32,
-                y: y as i32,
-                id: char,
-            })
+fn simplify(ring: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    // drop the closing point, then every point on a straight line
+    let n = ring.len() - 1;
+    let mut out: Vec<(i64, i64)> = (0..n)
+        .filter(|&i| {
+            let (a, b, c) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+            (b.0 - a.0) * (c.1 - b.1) != (b.1 - a.1) * (c.0 - b.0)
         })
-        .collect::<Vec<_>>();
+        .map(|i| ring[i])
+        .collect();
+    out.push(out[0]);
+    out
+}
 
-    let nodes_set = nodes.iter().cloned().collect::<HashSet<_>>();
+#[derive(Debug, Clone)]
+pub struct Garden {
+    width: usize,
+    height: usize,
+    plants: Vec<char>,
+}
 
-    let mut node_to_group: HashMap<&Node, usize> = HashMap::new();
-    let mut group_to_node: HashMap<usize, Vec<&Node>> = HashMap::new();
-    let mut group_to_area_permimiter: HashMap<usize, (u32, u32)> = HashMap::new();
+impl FromStr for Garden {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let width = s.lines().next().ok_or(ParseError)?.chars().count();
+        let mut plants = Vec::new();
+        let mut height = 0;
+        for line in s.lines() {
+            let len = plants.len();
+            plants.extend(line.chars());
+            if plants.len() - len != width {
+                return Err(ParseError);
+            }
+            height += 1;
+        }
 
-    for (i, node) in nodes.iter().enumerate() {
-        let all_neighbours = node.get_neighbours();
-        let neighbours: Vec<_> = all_neighbours
-            .iter()
-            .filter_map(|neighbour| nodes_set.get(&neighbour))
-            .collect();
+        Ok(Garden {
+            width,
+            height,
+            plants,
+        })
+    }
+}
 
-        let perimiter = (all_neighbours.len() - neighbours.len()) as u32;
+impl Garden {
+    pub fn width(&self) -> usize {
+        self.width
+    }
 
-        let mut groups: Vec<_> = neighbours
-            .into_iter()
-            .filter_map(|neighbour| node_to_group.get(&neighbour))
-            .map(|group| *group)
-            .collect();
+    pub fn height(&self) -> usize {
+        self.height
+    }
 
-        // add new group
-        if groups.len() == 0 {
-            groups.push(i);
+    pub fn plant_at(&self, pos: Pos) -> Option<char> {
+        if pos.x >= self.width || pos.y >= self.height {
+            return None;
         }
+        Some(self.plants[pos.y * self.width + pos.x])
+    }
 
-        groups.sort();
-        groups.dedup();
-        let group = groups[0];
-        let to_merge = &groups[1..];
-
-        node_to_group.entry(&node).or_insert(group);
-        group_to_node.entry(group).or_insert(Vec::new()).push(node);
-        let (area, per) = group_to_area_permimiter.entry(group).or_insert((0, 0));
-        *area += 1;
-        *per += perimiter;
-
-        for neighbour_group in to_merge {
-            // copy area, perimiter to result
-            let (area, per) = *group_to_area_permimiter.get(neighbour_group).unwrap();
-            let (new_area, new_per): &mut (u32, u32) =
-                group_to_area_permimiter.get_mut(&group).unwrap();
-            *new_area += area;
-            *new_per += per;
-
-            // copy nodes to new group
-            let to_copy: Vec<&Node> = group_to_node.get(&neighbour_group).unwrap().clone();
-            group_to_node
-                .get_mut(&group)
-                .unwrap()
-                .extend(to_copy.clone());
-
-            // update node lookup
-            for n in &to_copy {
-                let g = node_to_group.get_mut(n).unwrap();
-                *g = group;
-            }
+    // the plant at an offset from `pos`, or `None` off the map
+    fn neighbour(&self, pos: Pos, dx: isize, dy: isize) -> Option<char> {
+        let x = pos.x.checked_add_signed(dx)?;
+        let y = pos.y.checked_add_signed(dy)?;
+        self.plant_at(Pos { x, y })
+    }
+
+    /// All regions with their cells, ordered by their first cell in
+    /// reading order.
+    pub fn regions(&self) -> Vec<Region> {
+        let labelling = self.label();
+        let mut regions: Vec<Region> = labelling
+            .regions
+            .iter()
+            .map(|stats| Region {
+                plant: stats.plant,
+                cells: Vec::with_capacity(stats.area),
+                area: stats.area,
+                perimeter: stats.perimeter,
+                sides: stats.sides,
+                holes: stats.holes,
+                bounds: stats.bounds,
+            })
+            .collect();
 
-            group_to_area_permimiter.remove(&neighbour_group);
-            group_to_node.remove(&neighbour_group);
+        for (i, &label) in labelling.labels.iter().enumerate() {
+            regions[label as usize].cells.push(self.pos(i));
         }
+        regions
     }
 
-    group_to_area_permimiter
-        .into_iter()
-        .map(|(_, (area, per))| area * per)
-        .sum()
-}
-
-fn count_sides_horizontal(points: &mut Vec<&Node>, nodes_set: &HashSet<Node>) -> u32 {
-    let mut sides = 0;
+    fn pos(&self, i: usize) -> Pos {
+        Pos {
+            x: i % self.width,
+            y: i / self.width,
+        }
+    }
 
-    for dir in [Node::get_neighbour_up, Node::get_neighbour_down] {
-        // sort points horizontally
-        points.sort_by_key(|point| (point.y, point.x));
-        let mut prev: &Node = points[0];
-        let mut prev_was_edge = false;
+    /// Labels every cell with its region in one pass, joining the labels
+    /// of the cell above and to the left as it goes.
+    pub fn label(&self) -> Labelling {
+        let mut labels = vec![0u32; self.plants.len()];
+        let mut sets = DisjointSet::default();
+        let mut tallies: Vec<Tally> = vec![];
+
+        for i in 0..self.plants.len() {
+            let pos = self.pos(i);
+            let plant = self.plants[i];
+            let left = (pos.x > 0 && self.plants[i - 1] == plant).then(|| labels[i - 1]);
+            let up =
+                (pos.y > 0 && self.plants[i - self.width] == plant).then(|| labels[i - self.width]);
+
+            let label = match (left, up) {
+                (Some(a), Some(b)) => {
+                    if let Some((root, absorbed)) = sets.union(a, b) {
+                        let absorbed = tallies[absorbed as usize];
+                        tallies[root as usize].merge(&absorbed);
+                    }
+                    a
+                }
+                (Some(a), None) | (None, Some(a)) => a,
+                (None, None) => {
+                    tallies.push(Tally::new(plant, pos));
+                    sets.push()
+                }
+            };
 
-        let is_edge = !nodes_set.contains(&dir(prev));
-        if is_edge {
-            sides += 1;
-            prev_was_edge = true;
+            labels[i] = label;
+            let root = sets.find(label) as usize;
+            tallies[root].add(self, pos);
         }
 
-        for point in &points[1..] {
-            if prev.y != point.y {
-                prev_was_edge = false;
-            }
-
-            let is_edge = !nodes_set.contains(&dir(point));
-            if !is_edge {
-                prev_was_edge = false;
-            } else {
-                if !prev_was_edge || prev.get_neighbour_right() != **point {
-                    sides += 1;
-                    prev_was_edge = true;
-                }
+        // number the regions by their first cell
+        let mut index = vec![u32::MAX; tallies.len()];
+        let mut regions = vec![];
+        for label in labels.iter_mut() {
+            let root = sets.find(*label) as usize;
+            if index[root] == u32::MAX {
+                index[root] = regions.len() as u32;
+                regions.push(tallies[root].stats());
             }
+            *label = index[root];
+        }
 
-            prev = point;
+        Labelling {
+            width: self.width,
+            labels,
+            regions,
         }
     }
-
-    return sides;
 }
 
-fn count_sides_vertical(points: &mut Vec<&Node>, nodes_set: &HashSet<Node>) -> u32 {
-    let mut sides = 0;
-
-    for dir in [Node::get_neighbour_left, Node::get_neighbour_right] {
-        // sort points horizontally
-        points.sort_by_key(|point| (point.x, point.y));
-        let mut prev: &Node = points[0];
-        let mut prev_was_edge: bool = false;
+/// Area, fences and extent of one region, without its cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionStats {
+    pub plant: char,
+    pub area: usize,
+    pub perimeter: usize,
+    pub sides: usize,
+    pub holes: usize,
+    pub bounds: BoundingBox,
+}
 
-        let is_edge = !nodes_set.contains(&dir(prev));
-        if is_edge {
-            sides += 1;
-            prev_was_edge = true;
-        }
+impl RegionStats {
+    pub fn price(&self) -> usize {
+        self.area * self.perimeter
+    }
 
-        // check up
-        for point in &points[1..] {
-            if prev.x != point.x {
-                prev_was_edge = false;
-            }
+    pub fn bulk_price(&self) -> usize {
+        self.area * self.sides
+    }
+}
 
-            let is_edge = !nodes_set.contains(&dir(point));
-            if !is_edge {
-                prev_was_edge = false;
-            } else {
-                if !prev_was_edge || prev.get_neighbour_down() != **point {
-                    sides += 1;
-                    prev_was_edge = true;
-                }
-            }
+/// The region of every cell in a garden.
+#[derive(Debug, Clone)]
+pub struct Labelling {
+    width: usize,
+    labels: Vec<u32>,
+    regions: Vec<RegionStats>,
+}
 
-            prev = point;
+impl Labelling {
+    /// Index into [`Labelling::regions`] of the region holding `pos`.
+    pub fn label_at(&self, pos: Pos) -> Option<usize> {
+        if pos.x >= self.width {
+            return None;
         }
+        let label = self.labels.get(pos.y * self.width + pos.x)?;
+        Some(*label as usize)
     }
 
-    return sides;
+    pub fn regions(&self) -> &[RegionStats] {
+        &self.regions
+    }
 }
 
-fn count_sides(points: &mut Vec<&Node>, nodes_set: &HashSet<Node>) -> u32 {
-    // count up down left right
-    return count_sides_horizontal(points, nodes_set) + count_sides_vertical(points, nodes_set);
+// running totals for a label, kept on the root of its set; there can be
+// almost one per cell, so they are kept small
+#[derive(Debug, Clone, Copy)]
+struct Tally {
+    plant: char,
+    area: u32,
+    perimeter: u32,
+    convex: u32,
+    concave: u32,
+    // min x, min y, max x, max y
+    bounds: [u32; 4],
 }
 
-pub fn part2(input: &str) -> u32 {
-    let nodes = input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars().enumerate().map(move |(x, char)| Node {
-                x: x as i32,
-                y: y as i32,
-                id: char,
-            })
-        })
-        .collect::<Vec<_>>();
-    let nodes_set = nodes.iter().cloned().collect::<HashSet<_>>();
+impl Tally {
+    fn new(plant: char, pos: Pos) -> Self {
+        let (x, y) = (pos.x as u32, pos.y as u32);
+        Tally {
+            plant,
+            area: 0,
+            perimeter: 0,
+            convex: 0,
+            concave: 0,
+            bounds: [x, y, x, y],
+        }
+    }
+
+    fn add(&mut self, garden: &Garden, pos: Pos) {
+        let same = |dx, dy| garden.neighbour(pos, dx, dy) == Some(self.plant);
+        self.area += 1;
+        for (dx, dy) in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+            if !same(dx, dy) {
+                self.perimeter += 1;
+            }
+        }
 
-    let mut group_to_points = HashMap::new();
-    let mut group_to_node = HashMap::new();
-    let mut node_to_group = HashMap::new();
+        for (dx, dy) in [(-1, -1), (1, -1), (1, 1), (-1, 1)] {
+            match (same(dx, 0), same(0, dy), same(dx, dy)) {
+                (false, false, _) => self.convex += 1,
+                (true, true, false) => self.concave += 1,
+                _ => {}
+            }
+        }
 
-    for (i, node) in nodes.iter().enumerate() {
-        let all_neighbours = node.get_neighbours();
-        let neighbours: Vec<_> = all_neighbours
-            .iter()
-            .filter_map(|neighbour| nodes_set.get(&neighbour))
-            .collect();
+        let (x, y) = (pos.x as u32, pos.y as u32);
+        self.merge_bounds([x, y, x, y]);
+    }
 
-        let mut groups: Vec<_> = neighbours
-            .into_iter()
-            .filter_map(|neighbour| node_to_group.get(&neighbour))
-            .map(|group| *group)
-            .collect();
+    fn merge(&mut self, other: &Tally) {
+        self.area += other.area;
+        self.perimeter += other.perimeter;
+        self.convex += other.convex;
+        self.concave += other.concave;
+        self.merge_bounds(other.bounds);
+    }
+
+    fn merge_bounds(&mut self, [min_x, min_y, max_x, max_y]: [u32; 4]) {
+        let b = &mut self.bounds;
+        *b = [
+            b[0].min(min_x),
+            b[1].min(min_y),
+            b[2].max(max_x),
+            b[3].max(max_y),
+        ];
+    }
 
-        // add new group
-        if groups.len() == 0 {
-            groups.push(i);
+    fn stats(&self) -> RegionStats {
+        // Euler number of a 4-connected region: one piece minus its holes
+        let holes = 1 - (self.convex as i64 - self.concave as i64) / 4;
+        let [min_x, min_y, max_x, max_y] = self.bounds.map(|v| v as usize);
+
+        RegionStats {
+            plant: self.plant,
+            area: self.area as usize,
+            perimeter: self.perimeter as usize,
+            sides: (self.convex + self.concave) as usize,
+            holes: holes as usize,
+            bounds: BoundingBox {
+                min: Pos { x: min_x, y: min_y },
+                max: Pos { x: max_x, y: max_y },
+            },
         }
+    }
+}
 
-        groups.sort();
-        groups.dedup();
-        let group = groups[0];
-        let to_merge = &groups[1..];
-
-        node_to_group.entry(node).or_insert(group);
-        group_to_node.entry(group).or_insert(Vec::new()).push(node);
-        let points = group_to_points.entry(group).or_insert(Vec::new());
-        points.push(node);
-
-        for neighbour_group in to_merge {
-            // merge polygon
-            let old_nodes = group_to_points.remove(neighbour_group).unwrap();
-            let new_nodes = group_to_points.get_mut(&group).unwrap();
-            new_nodes.extend(old_nodes);
-
-            // copy nodes to new group
-            let to_copy: Vec<&Node> = group_to_node.get(&neighbour_group).unwrap().clone();
-            group_to_node
-                .get_mut(&group)
-                .unwrap()
-                .extend(to_copy.clone());
-
-            // update node lookup
-            for n in &to_copy {
-                let g = node_to_group.get_mut(n).unwrap();
-                *g = group;
-            }
+/// Union-find over labels, with union by size and path halving.
+#[derive(Debug, Clone, Default)]
+struct DisjointSet {
+    parent: Vec<u32>,
+    size: Vec<u32>,
+}
+
+impl DisjointSet {
+    fn push(&mut self) -> u32 {
+        let label = self.parent.len() as u32;
+        self.parent.push(label);
+        self.size.push(1);
+        label
+    }
 
-            group_to_node.remove(&neighbour_group);
+    fn find(&mut self, mut label: u32) -> u32 {
+        while self.parent[label as usize] != label {
+            let grandparent = self.parent[self.parent[label as usize] as usize];
+            self.parent[label as usize] = grandparent;
+            label = grandparent;
         }
+        label
     }
 
-    let mut result = 0;
-    for (group, points) in group_to_points.iter_mut() {
-        let sides = count_sides(points, &nodes_set);
-        result += sides * points.len() as u32;
+    // returns the new root and the root it absorbed, if they were apart
+    fn union(&mut self, a: u32, b: u32) -> Option<(u32, u32)> {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return None;
+        }
+        if self.size[a as usize] < self.size[b as usize] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b as usize] = a;
+        self.size[a as usize] += self.size[b as usize];
+        Some((a, b))
     }
+}
+
+pub fn part1(input: &str) -> u64 {
+    let garden = input.parse::<Garden>().expect("could not parse garden");
+    let labelling = garden.label();
+    labelling
+        .regions()
+        .iter()
+        .map(RegionStats::price)
+        .sum::<usize>() as u64
+}
 
-    result
+pub fn part2(input: &str) -> u64 {
+    let garden = input.parse::<Garden>().expect("could not parse garden");
+    let labelling = garden.label();
+    labelling
+        .regions()
+        .iter()
+        .map(RegionStats::bulk_price)
+        .sum::<usize>() as u64
 }
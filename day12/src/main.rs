@@ -1,4 +1,8 @@
 use day12::{part1, part2};
+#[cfg(test)]
+use day12::{BoundingBox, Garden, Pos, RegionStats};
+#[cfg(test)]
+use geo::Area;
 use std::fs;
 
 
@@ -23,13 +27,13 @@ fn test_part_1() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part1(&contents);
     assert_eq!(num, 1930);
 
-    return Ok(());
+    Ok(())
 }
 
 #[test]
@@ -37,11 +41,100 @@ fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part2(&contents);
     assert_eq!(num, 1206);
 
-    return Ok(());
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[test]
+fn test_regions() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "input/dummy.txt";
+
+    // Read file contents
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+
+    let garden: Garden = contents.parse().map_err(|_| "invalid garden")?;
+    let regions = garden.regions();
+    assert_eq!(regions.len(), 11);
+
+    let r = &regions[0];
+    assert_eq!(
+        (r.plant, r.area, r.perimeter, r.sides, r.holes),
+        ('R', 12, 18, 10, 0)
+    );
+    assert_eq!(
+        r.bounds,
+        BoundingBox {
+            min: Pos { x: 0, y: 0 },
+            max: Pos { x: 4, y: 3 }
+        }
+    );
+    assert_eq!(r.to_polygon().unsigned_area(), 12.0);
+
+    // the two B blocks meet at a corner and make up a single hole
+    let garden: Garden = "AAAAAA\nAAABBA\nAAABBA\nABBAAA\nABBAAA\nAAAAAA"
+        .parse()
+        .map_err(|_| "invalid garden")?;
+    let regions = garden.regions();
+    assert_eq!(regions.len(), 3);
+    assert_eq!((regions[0].sides, regions[0].holes), (12, 1));
+    assert_eq!(regions[0].to_polygon().interiors().len(), 1);
+    assert_eq!(regions[0].to_polygon().unsigned_area(), 28.0);
+    assert_eq!(regions.iter().map(|r| r.bulk_price()).sum::<usize>(), 368);
+
+    let garden: Garden = "AAA\nABA\nAAA".parse().map_err(|_| "invalid garden")?;
+    let polygon = garden.regions()[0].to_polygon();
+    assert_eq!(garden.regions()[0].holes, 1);
+    assert_eq!(polygon.exterior().0.len(), 5);
+    assert_eq!(polygon.interiors().len(), 1);
+
+    // the B pocket reaches the outside through the bottom right corner
+    let garden: Garden = "AAA\nABA\nAAB".parse().map_err(|_| "invalid garden")?;
+    let polygon = garden.regions()[0].to_polygon();
+    assert_eq!(garden.regions()[0].holes, 0);
+    assert_eq!(polygon.interiors().len(), 0);
+    assert_eq!(polygon.unsigned_area(), 7.0);
+
+    Ok(())
+}
+
+#[test]
+fn test_labelling() -> Result<(), Box<dyn std::error::Error>> {
+    // the two arms of the U get separate labels until the bottom row joins them
+    let garden: Garden = "ABA\nABA\nAAA".parse().map_err(|_| "invalid garden")?;
+    let labelling = garden.label();
+    assert_eq!(labelling.regions().len(), 2);
+    assert_eq!(labelling.label_at(Pos { x: 2, y: 0 }), Some(0));
+    assert_eq!(labelling.label_at(Pos { x: 1, y: 1 }), Some(1));
+    assert_eq!(labelling.label_at(Pos { x: 3, y: 0 }), None);
+    assert_eq!(
+        labelling.regions()[0],
+        RegionStats {
+            plant: 'A',
+            area: 7,
+            perimeter: 16,
+            sides: 8,
+            holes: 0,
+            bounds: BoundingBox {
+                min: Pos { x: 0, y: 0 },
+                max: Pos { x: 2, y: 2 }
+            }
+        }
+    );
+
+    // a comb whose teeth only meet along the bottom row
+    let row = "AB".repeat(100);
+    let input = format!("{}\n", row).repeat(99) + &"A".repeat(200);
+    let garden: Garden = input.parse().map_err(|_| "invalid garden")?;
+    let labelling = garden.label();
+    assert_eq!(labelling.regions().len(), 101);
+    assert_eq!(labelling.regions()[0].area, 100 * 99 + 200);
+    assert_eq!(garden.regions()[0].cells.len(), 100 * 99 + 200);
+
+    Ok(())
+}
@@ -1,193 +1,474 @@
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq)]
-struct Line {
-    a: i64,
-    b: i64,
-    c: i64,
-}
-
-impl Line {
-    fn new(step_x: i64, step_y: i64, point: &Pos) -> Self {
-        let (x1, y1) = (point.x, point.y);
-        let a = step_y;
-        let b = -(step_x);
-        let c = ((-b) * y1) - (a * x1);
-
-        Line { a, b, c }
-    }
-
-    fn intersection(&self, other: &Line) -> Pos {
-        let a1 = self.a;
-        let a2 = other.a;
-
-        let b1 = self.b;
-        let b2 = other.b;
-
-        let c1 = self.c;
-        let c2 = other.c;
-
-        let x = (b1 * c2 - b2 * c1) / (a1 * b2 - a2 * b1);
-        let y = (c1 * a2 - c2 * a1) / (a1 * b2 - a2 * b1);
-
-        Pos { x, y }
-    }
-}
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseError;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-struct Pos {
-    x: i64,
-    y: i64,
+pub struct Pos {
+    pub x: i64,
+    pub y: i64,
 }
 
 impl FromStr for Pos {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let xy = s
             .split(',')
-            .map(|x| x.split('=').last().ok_or(()))
+            .map(|x| x.split('=').next_back().ok_or(ParseError))
             .collect::<Result<Vec<_>, _>>()?;
+        if xy.len() != 2 {
+            return Err(ParseError);
+        }
 
-        let x = xy[0].parse::<i64>().map_err(|_| ())?;
-        let y = xy[1].parse::<i64>().map_err(|_| ())?;
+        let x = xy[0].trim().parse::<i64>().map_err(|_| ParseError)?;
+        let y = xy[1].trim().parse::<i64>().map_err(|_| ParseError)?;
 
         Ok(Pos { x, y })
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct Button {
-    name: String,
-    cost: u32,
-    x: i64,
-    y: i64,
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Button {
+    pub name: String,
+    /// Tokens per press.
+    pub cost: u64,
+    pub x: i64,
+    pub y: i64,
+}
+
+// the tokens per press of the buttons the puzzle prices
+fn puzzle_cost(name: &str) -> Option<u64> {
+    match name {
+        "Button A" => Some(3),
+        "Button B" => Some(1),
+        _ => None,
+    }
 }
 
 impl FromStr for Button {
-    type Err = ();
+    type Err = ParseError;
 
+    /// Only the puzzle's `Button A` and `Button B` have a known cost.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut split = s.split(':');
-        let name = split.next().ok_or(())?;
-        let name = name.to_string();
-
-        let xy = s
-            .split(',')
-            .map(|x| x.split('+').last().ok_or(()))
-            .collect::<Result<Vec<_>, _>>()?;
+        parse_button(s, puzzle_cost)
+    }
+}
 
-        let x = xy[0].parse::<i64>().map_err(|_| ())?;
-        let y = xy[1].parse::<i64>().map_err(|_| ())?;
+fn parse_button(s: &str, cost: impl FnOnce(&str) -> Option<u64>) -> Result<Button, ParseError> {
+    let (name, moves) = s.split_once(':').ok_or(ParseError)?;
 
-        let cost = if name == "Button A" {
-            3
-        } else if name == "Button B" {
-            1
-        } else {
-            unimplemented!()
-        };
-
-        Ok(Button {
-            cost,
-            name: name.to_string(),
-            x,
-            y,
-        })
+    let xy = moves
+        .split(',')
+        .map(|x| x.split('+').next_back().ok_or(ParseError))
+        .collect::<Result<Vec<_>, _>>()?;
+    if xy.len() != 2 {
+        return Err(ParseError);
     }
+
+    let x = xy[0].trim().parse::<i64>().map_err(|_| ParseError)?;
+    let y = xy[1].trim().parse::<i64>().map_err(|_| ParseError)?;
+
+    Ok(Button {
+        cost: cost(name).ok_or(ParseError)?,
+        name: name.to_string(),
+        x,
+        y,
+    })
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct Machine {
-    buttons: Vec<Button>,
-    prize: Pos,
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Machine {
+    pub buttons: Vec<Button>,
+    pub prize: Pos,
 }
 
 impl FromStr for Machine {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lines: Vec<_> = s.lines().collect();
-        let buttons = lines[0..lines.len() - 1]
+        Machine::parse(s, |_, name| puzzle_cost(name))
+    }
+}
+
+impl Machine {
+    /// Parses a machine with buttons of any name, the i-th of which costs
+    /// `costs[i]` tokens per press.
+    pub fn parse_with_costs(s: &str, costs: &[u64]) -> Result<Self, ParseError> {
+        let machine = Machine::parse(s, |i, _| costs.get(i).copied())?;
+        if machine.buttons.len() != costs.len() {
+            return Err(ParseError);
+        }
+        Ok(machine)
+    }
+
+    fn parse(s: &str, cost: impl Fn(usize, &str) -> Option<u64>) -> Result<Self, ParseError> {
+        let lines: Vec<_> = s.lines().filter(|line| !line.trim().is_empty()).collect();
+        let (prize, buttons) = lines.split_last().ok_or(ParseError)?;
+        let buttons = buttons
             .iter()
-            .take_while(|line| line.starts_with("Button"))
-            .map(|line| line.parse::<Button>())
+            .enumerate()
+            .map(|(i, line)| parse_button(line, |name| cost(i, name)))
             .collect::<Result<Vec<_>, _>>()?;
 
-        let prize = lines.last().expect("invalid line")["Prize: ".len()..].parse::<Pos>()?;
+        let prize = prize
+            .strip_prefix("Prize: ")
+            .ok_or(ParseError)?
+            .parse::<Pos>()?;
         Ok(Machine { buttons, prize })
     }
 }
 
-pub fn part1(input: &str) -> u64 {
-    let machines = input
+pub fn parse_machines(input: &str) -> Result<Vec<Machine>, ParseError> {
+    input
         .split("\n\n")
-        .map(|m| m.parse::<Machine>().expect("invalid machine"));
+        .filter(|m| !m.trim().is_empty())
+        .map(|m| m.parse::<Machine>())
+        .collect()
+}
+
+/// The cheapest way to win a prize.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Plan {
+    /// Presses per button, in the machine's button order.
+    pub presses: Vec<u64>,
+    pub cost: u64,
+}
+
+// a button as (dx, dy, cost)
+type Move = (i128, i128, i128);
 
-    let mut result = 0;
+impl Machine {
+    /// Overrides the cost of each button, in order.
+    pub fn with_costs(mut self, costs: &[u64]) -> Self {
+        for (button, &cost) in self.buttons.iter_mut().zip(costs) {
+            button.cost = cost;
+        }
+        self
+    }
 
-    for machine in machines {
-        assert!(machine.buttons.len() == 2);
+    fn moves(&self) -> Vec<Move> {
+        self.buttons
+            .iter()
+            .map(|b| (b.x as i128, b.y as i128, b.cost as i128))
+            .collect()
+    }
 
-        let p = &machine.prize;
-        let (m1, m1_cost) = (&machine.buttons[0], machine.buttons[0].cost as i64);
-        let (m2, m2_cost) = (&machine.buttons[1], machine.buttons[1].cost as i64);
+    /// Finds the cheapest presses that land exactly on the prize, `None` if
+    /// there are none.
+    ///
+    /// Two buttons are solved directly, collinear ones included. Every button
+    /// after those is tried with each press count that does not overshoot the
+    /// prize, so the work grows with the prize. This fails if that means
+    /// trying more than [`MAX_SEARCH`] combinations of presses, or if any
+    /// button moves backwards, as nothing then bounds the presses.
+    pub fn solve(&self) -> Result<Option<Plan>, SearchTooLarge> {
+        let moves = self.moves();
+        let prize = (self.prize.x as i128, self.prize.y as i128);
+        let limits = search_limits(&moves, prize)?;
+
+        let plan = cheapest(&moves, &limits, prize).and_then(|(presses, cost)| {
+            Some(Plan {
+                presses: presses
+                    .into_iter()
+                    .map(|n| u64::try_from(n).ok())
+                    .collect::<Option<_>>()?,
+                cost: u64::try_from(cost).ok()?,
+            })
+        });
+        Ok(plan)
+    }
+}
 
-        let l1 = Line::new(m1.x, m1.y, &Pos { x: 0, y: 0 });
-        let l2 = Line::new(m2.x, m2.y, &machine.prize);
-        let intersect = l1.intersection(&l2);
+/// The most combinations of presses of the buttons after the first two that
+/// [`Machine::solve`] tries.
+pub const MAX_SEARCH: u64 = 10_000_000;
 
-        // check if the intersection point could ever be reached by both lines
-        if intersect.x % m1.x == 0
-            && (p.x - intersect.x) % m2.x == 0
-            && intersect.x >= 0
-            && intersect.x <= p.x
-        {
-            let cost = (intersect.x / m1.x).abs() * m1_cost;
-            let cost = cost + ((p.x - intersect.x) / m2.x).abs() * m2_cost;
-            let cost = cost as u64;
+/// Solving a machine would take more than [`MAX_SEARCH`] combinations of
+/// presses, or has no bound at all.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SearchTooLarge;
+
+/// Moved onto both coordinates of every prize.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct PrizeOffset(pub i64);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum MaxPresses {
+    /// No button may be pressed more often than this.
+    Limit(u64),
+    #[default]
+    Unlimited,
+}
 
-            result += cost;
+impl MaxPresses {
+    fn limit(self) -> Option<i128> {
+        match self {
+            MaxPresses::Limit(n) => Some(n as i128),
+            MaxPresses::Unlimited => None,
         }
     }
+}
 
-    result
+/// The cheapest way to win a two-button machine.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Solution {
+    pub presses_a: u64,
+    pub presses_b: u64,
+    pub cost: u64,
 }
 
-pub fn part2(input: &str) -> u64 {
-    let machines = input
-        .split("\n\n")
-        .map(|m| m.parse::<Machine>().expect("invalid machine"));
+/// Solves every two-button machine, giving `None` for prizes that cannot be
+/// won, machines without exactly two buttons, and answers too large to
+/// compute.
+pub fn solve(
+    machines: &[Machine],
+    offset: PrizeOffset,
+    max_presses: MaxPresses,
+) -> Vec<Option<Solution>> {
+    machines
+        .iter()
+        .map(|machine| {
+            let [a, b] = machine.moves()[..] else {
+                return None;
+            };
+            let prize = (
+                (machine.prize.x as i128).checked_add(offset.0 as i128)?,
+                (machine.prize.y as i128).checked_add(offset.0 as i128)?,
+            );
+
+            let (n, m) = pair(a, b, prize, max_presses.limit())?;
+            let cost = cost(&[(n, a.2), (m, b.2)])?;
+            Some(Solution {
+                presses_a: u64::try_from(n).ok()?,
+                presses_b: u64::try_from(m).ok()?,
+                cost: u64::try_from(cost).ok()?,
+            })
+        })
+        .collect()
+}
 
-    let mut result = 0;
+// total of presses * cost, or `None` on overflow
+fn cost(presses: &[(i128, i128)]) -> Option<i128> {
+    presses
+        .iter()
+        .try_fold(0i128, |sum, &(n, c)| sum.checked_add(n.checked_mul(c)?))
+}
 
-    for machine in machines {
-        assert!(machine.buttons.len() == 2);
+// how often each button after the first two may be pressed
+fn search_limits(moves: &[Move], prize: (i128, i128)) -> Result<Vec<i128>, SearchTooLarge> {
+    let extra = moves.get(2..).unwrap_or_default();
+    if extra.is_empty() {
+        return Ok(vec![]);
+    }
+    if moves.iter().any(|&(dx, dy, _)| dx < 0 || dy < 0) {
+        return Err(SearchTooLarge);
+    }
 
-        let mut p = machine.prize;
-        p.x += 10000000000000;
-        p.y += 10000000000000;
+    let limits: Vec<i128> = extra
+        .iter()
+        .map(|&(dx, dy, _)| press_limit((dx, dy), prize))
+        .collect();
+    let combinations = limits
+        .iter()
+        .try_fold(1u64, |n, &limit| n.checked_mul(limit as u64 + 1));
+    match combinations {
+        Some(n) if n <= MAX_SEARCH => Ok(limits),
+        _ => Err(SearchTooLarge),
+    }
+}
 
-        let (m1, m1_cost) = (&machine.buttons[0], machine.buttons[0].cost as i64);
-        let (m2, m2_cost) = (&machine.buttons[1], machine.buttons[1].cost as i64);
+// the most presses before a button overshoots the prize when no button moves
+// backwards, a button that does not move is never worth pressing
+fn press_limit((dx, dy): (i128, i128), (px, py): (i128, i128)) -> i128 {
+    if px < 0 || py < 0 {
+        return 0;
+    }
+    [(dx, px), (dy, py)]
+        .into_iter()
+        .filter(|&(d, _)| d > 0)
+        .map(|(d, p)| p / d)
+        .min()
+        .unwrap_or(0)
+}
 
-        let l1 = Line::new(m1.x, m1.y, &Pos { x: 0, y: 0 });
-        let l2 = Line::new(m2.x, m2.y, &p);
-        let intersect = l1.intersection(&l2);
+// all arithmetic is checked, with overflow treated as no solution; `limits`
+// bounds the presses of the buttons after the first two
+fn cheapest(moves: &[Move], limits: &[i128], prize: (i128, i128)) -> Option<(Vec<i128>, i128)> {
+    match moves {
+        [] => (prize == (0, 0)).then(|| (vec![], 0)),
+        [a] => {
+            let n = single(*a, prize, None)?;
+            Some((vec![n], cost(&[(n, a.2)])?))
+        }
+        [a, b] => {
+            let (n, m) = pair(*a, *b, prize, None)?;
+            Some((vec![n, m], cost(&[(n, a.2), (m, b.2)])?))
+        }
+        [rest @ .., last] => {
+            let (dx, dy, c) = *last;
+            let (&limit, rest_limits) = limits.split_last()?;
+
+            let mut best: Option<(Vec<i128>, i128)> = None;
+            for n in 0..=limit {
+                let own = n.checked_mul(c)?;
+                if best.as_ref().is_some_and(|(_, b)| own >= *b) {
+                    break;
+                }
+                let remaining = (prize.0 - n * dx, prize.1 - n * dy);
+                if let Some((mut presses, rest_cost)) = cheapest(rest, rest_limits, remaining) {
+                    let total = rest_cost.checked_add(own)?;
+                    if best.as_ref().is_none_or(|(_, b)| total < *b) {
+                        presses.push(n);
+                        best = Some((presses, total));
+                    }
+                }
+            }
+            best
+        }
+    }
+}
+
+fn within(n: i128, max: Option<i128>) -> bool {
+    n >= 0 && max.is_none_or(|max| n <= max)
+}
 
-        // check if the intersection point could ever be reached by both lines
-        if intersect.x % m1.x == 0
-            && (p.x - intersect.x) % m2.x == 0
-            && intersect.x >= 0
-            && intersect.x <= p.x
-        {
-            let cost = (intersect.x / m1.x).abs() * m1_cost;
-            let cost = cost + ((p.x - intersect.x) / m2.x).abs() * m2_cost;
-            let cost = cost as u64;
+fn single((dx, dy, _): Move, (px, py): (i128, i128), max: Option<i128>) -> Option<i128> {
+    if (dx, dy) == (0, 0) {
+        return ((px, py) == (0, 0)).then_some(0);
+    }
+    let n = if dx != 0 { px / dx } else { py / dy };
+    let hits = n.checked_mul(dx)? == px && n.checked_mul(dy)? == py;
+    (hits && within(n, max)).then_some(n)
+}
 
-            result += cost;
+fn pair(a: Move, b: Move, (px, py): (i128, i128), max: Option<i128>) -> Option<(i128, i128)> {
+    let det = a.0.checked_mul(b.1)?.checked_sub(a.1.checked_mul(b.0)?)?;
+    if det != 0 {
+        // one crossing point, which has to be a whole number of presses
+        let n = px.checked_mul(b.1)?.checked_sub(py.checked_mul(b.0)?)?;
+        let m = a.0.checked_mul(py)?.checked_sub(a.1.checked_mul(px)?)?;
+        if n % det != 0 || m % det != 0 {
+            return None;
         }
+        let (n, m) = (n / det, m / det);
+        return (within(n, max) && within(m, max)).then_some((n, m));
+    }
+
+    // a button that does not move is never worth pressing
+    if (a.0, a.1) == (0, 0) {
+        return single(b, (px, py), max).map(|m| (0, m));
+    }
+    if (b.0, b.1) == (0, 0) {
+        return single(a, (px, py), max).map(|n| (n, 0));
+    }
+
+    // both buttons run along one line, which must also hold the prize
+    if a.0.checked_mul(py)? != a.1.checked_mul(px)? {
+        return None;
     }
+    let (ka, kb, t) = if a.0 != 0 {
+        (a.0, b.0, px)
+    } else {
+        (a.1, b.1, py)
+    };
+    cheapest_on_line((ka, a.2), (kb, b.2), t, max)
+}
 
-    result
+// cheapest n, m in 0..=max with ka * n + kb * m = t
+fn cheapest_on_line(
+    (ka, ca): (i128, i128),
+    (kb, cb): (i128, i128),
+    t: i128,
+    max: Option<i128>,
+) -> Option<(i128, i128)> {
+    let (g, x, y) = extended_gcd(ka, kb);
+    if t % g != 0 {
+        return None;
+    }
+
+    // every solution is (n0 + dn * s, m0 + dm * s)
+    let (n0, m0) = (x.checked_mul(t / g)?, y.checked_mul(t / g)?);
+    let (dn, dm) = (kb / g, -ka / g);
+
+    let mut lo: Option<i128> = None;
+    let mut hi: Option<i128> = None;
+    let mut at_least = |bound: i128| lo = Some(lo.map_or(bound, |lo| lo.max(bound)));
+    let mut at_most = |bound: i128| hi = Some(hi.map_or(bound, |hi| hi.min(bound)));
+    for (v0, dv) in [(n0, dn), (m0, dm)] {
+        // 0 <= v0 + dv * s <= max
+        if dv > 0 {
+            at_least(div_ceil(-v0, dv));
+            if let Some(max) = max {
+                at_most(div_floor(max.checked_sub(v0)?, dv));
+            }
+        } else {
+            at_most(div_floor(v0, -dv));
+            if let Some(max) = max {
+                at_least(div_ceil(v0.checked_sub(max)?, -dv));
+            }
+        }
+    }
+    if let (Some(lo), Some(hi)) = (lo, hi) {
+        if lo > hi {
+            return None;
+        }
+    }
+
+    // the cost is linear in s, so the cheapest end of the range wins
+    let slope = ca * dn + cb * dm;
+    let s = if slope > 0 { lo } else { hi.or(lo) }?;
+    Some((
+        n0.checked_add(dn.checked_mul(s)?)?,
+        m0.checked_add(dm.checked_mul(s)?)?,
+    ))
+}
+
+// (g, x, y) with a * x + b * y = g and g > 0
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+fn div_floor(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if (a % b != 0) && ((a < 0) != (b < 0)) {
+        q - 1
+    } else {
+        q
+    }
+}
+
+fn div_ceil(a: i128, b: i128) -> i128 {
+    -div_floor(-a, b)
+}
+
+fn total_cost(solutions: &[Option<Solution>]) -> u64 {
+    solutions.iter().flatten().map(|s| s.cost).sum()
+}
+
+pub fn part1(input: &str) -> u64 {
+    let machines = parse_machines(input).expect("invalid machine");
+    total_cost(&solve(&machines, PrizeOffset(0), MaxPresses::Limit(100)))
+}
+
+pub fn part2(input: &str) -> u64 {
+    let machines = parse_machines(input).expect("invalid machine");
+    total_cost(&solve(
+        &machines,
+        PrizeOffset(10000000000000),
+        MaxPresses::Unlimited,
+    ))
 }
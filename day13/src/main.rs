@@ -1,4 +1,8 @@
 use day13::{part1, part2};
+#[cfg(test)]
+use day13::{
+    parse_machines, solve, Machine, MaxPresses, Plan, PrizeOffset, SearchTooLarge, Solution,
+};
 use std::fs;
 
 
@@ -23,13 +27,13 @@ fn test_part_1() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part1(&contents);
     assert_eq!(num, 480);
 
-    return Ok(());
+    Ok(())
 }
 
 #[test]
@@ -37,11 +41,141 @@ fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part2(&contents);
-    assert_eq!(num, 0);
+    assert_eq!(num, 875318608908);
+
+    Ok(())
+}
+
+#[test]
+fn test_solver() -> Result<(), Box<dyn std::error::Error>> {
+    let machine: Machine = "Button A: X+94, Y+34\nButton B: X+22, Y+67\nPrize: X=8400, Y=5400"
+        .parse()
+        .map_err(|_| "invalid machine")?;
+    assert_eq!(
+        machine.solve(),
+        Ok(Some(Plan {
+            presses: vec![80, 40],
+            cost: 280
+        }))
+    );
+
+    // collinear buttons, where A is the cheaper way to cover ground
+    let machine: Machine = "Button A: X+4, Y+4\nButton B: X+1, Y+1\nPrize: X=7, Y=7"
+        .parse()
+        .map_err(|_| "invalid machine")?;
+    assert_eq!(
+        machine.solve(),
+        Ok(Some(Plan {
+            presses: vec![1, 3],
+            cost: 6
+        }))
+    );
+    let machine = machine.with_costs(&[5, 1]);
+    assert_eq!(
+        machine.solve().map(|p| p.map(|p| p.presses)),
+        Ok(Some(vec![0, 7]))
+    );
+
+    let machine: Machine = "Button A: X+4, Y+2\nButton B: X+6, Y+3\nPrize: X=7, Y=7"
+        .parse()
+        .map_err(|_| "invalid machine")?;
+    assert_eq!(machine.solve(), Ok(None));
+
+    // any name and number of buttons, given their costs
+    let machine = "Button A: X+5, Y+0\nButton B: X+0, Y+5\nButton C: X+1, Y+1\nPrize: X=11, Y=6";
+    assert!(machine.parse::<Machine>().is_err());
+    assert!(Machine::parse_with_costs(machine, &[3, 1]).is_err());
+    let machine = Machine::parse_with_costs(machine, &[3, 1, 1]).map_err(|_| "invalid machine")?;
+    assert_eq!(machine.buttons[2].name, "Button C");
+    assert_eq!(
+        machine.solve(),
+        Ok(Some(Plan {
+            presses: vec![2, 1, 1],
+            cost: 8
+        }))
+    );
+    let machine = machine.with_costs(&[5, 5, 1]);
+    assert_eq!(
+        machine.solve().map(|p| p.map(|p| p.presses)),
+        Ok(Some(vec![1, 0, 6]))
+    );
+
+    // a button that does not move is never pressed
+    let machine = Machine::parse_with_costs(
+        "A: X+1, Y+0\nB: X+0, Y+1\nC: X+0, Y+0\nPrize: X=3, Y=3",
+        &[1, 1, 0],
+    )
+    .map_err(|_| "invalid machine")?;
+    assert_eq!(
+        machine.solve().map(|p| p.map(|p| p.presses)),
+        Ok(Some(vec![3, 3, 0]))
+    );
+
+    // the extra buttons are searched up to the prize, which has its limits
+    let machine = Machine::parse_with_costs(
+        "A: X+1, Y+0\nB: X+0, Y+1\nC: X+1, Y+1\nPrize: X=100000000, Y=100000000",
+        &[1, 1, 1],
+    )
+    .map_err(|_| "invalid machine")?;
+    assert_eq!(machine.solve(), Err(SearchTooLarge));
 
-    return Ok(());
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[test]
+fn test_solve() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "input/dummy.txt";
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+    let machines = parse_machines(&contents).map_err(|_| "invalid machine")?;
+
+    let solutions = solve(&machines, PrizeOffset(0), MaxPresses::Limit(100));
+    assert_eq!(
+        solutions,
+        vec![
+            Some(Solution {
+                presses_a: 80,
+                presses_b: 40,
+                cost: 280
+            }),
+            None,
+            Some(Solution {
+                presses_a: 38,
+                presses_b: 86,
+                cost: 200
+            }),
+            None
+        ]
+    );
+
+    // the third machine needs 86 presses of B
+    let solutions = solve(&machines, PrizeOffset(0), MaxPresses::Limit(85));
+    assert_eq!(solutions[0].map(|s| s.cost), Some(280));
+    assert_eq!(solutions[2], None);
+
+    let solutions = solve(
+        &machines,
+        PrizeOffset(10000000000000),
+        MaxPresses::Unlimited,
+    );
+    let won: Vec<bool> = solutions.iter().map(Option::is_some).collect();
+    assert_eq!(won, vec![false, true, false, true]);
+
+    // the presses of each button fit in a u64 even at the largest offset, but
+    // the cost of four tokens per step on both axes only does for smaller ones
+    let machines = parse_machines("Button A: X+1, Y+0\nButton B: X+0, Y+1\nPrize: X=10, Y=10")
+        .map_err(|_| "invalid machine")?;
+    let offset = PrizeOffset(i64::MAX / 4);
+    let cost = solve(&machines, offset, MaxPresses::Unlimited)[0].map(|s| s.cost);
+    assert_eq!(cost, Some(4 * (10 + i64::MAX as u64 / 4)));
+    assert_eq!(
+        solve(&machines, PrizeOffset(i64::MAX), MaxPresses::Unlimited),
+        vec![None]
+    );
+
+    Ok(())
+}
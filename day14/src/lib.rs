@@ -1,6 +1,4 @@
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::prelude::*;
+use std::collections::HashMap;
 use std::str::FromStr;
 
 type Step = Pos;
@@ -36,8 +34,8 @@ impl Robot {
         self.pos.x += self.step.x * n;
         self.pos.y += self.step.y * n;
 
-        self.pos.x = self.pos.x % width;
-        self.pos.y = self.pos.y % height;
+        self.pos.x %= width;
+        self.pos.y %= height;
 
         if self.pos.x < 0 {
             self.pos.x += width;
@@ -49,15 +47,19 @@ impl Robot {
     }
 }
 
-pub fn part1(input: &str, width: u32, height: u32) -> u32 {
-    let mut robots: Vec<_> = input
+fn parse_robots(input: &str) -> Vec<Robot> {
+    input
         .lines()
         .map(|line| line.split_once(' ').expect("invalid line"))
         .map(|(pos, step)| Robot {
             pos: pos[2..].parse::<Pos>().expect("invalid pos"),
             step: step[2..].parse::<Step>().expect("invalid step"),
         })
-        .collect();
+        .collect()
+}
+
+pub fn part1(input: &str, width: u32, height: u32) -> u32 {
+    let mut robots = parse_robots(input);
 
     // simulate robots
     for robot in &mut robots {
@@ -89,75 +91,93 @@ pub fn part1(input: &str, width: u32, height: u32) -> u32 {
         }
     }
 
-    quarters.iter().fold(1, |acc, i| acc * i)
+    quarters.iter().product()
 }
 
-pub fn part2(input: &str, width: u32, height: u32) -> u32 {
-    let mut robots: Vec<_> = input
-        .lines()
-        .map(|line| line.split_once(' ').expect("invalid line"))
-        .map(|(pos, step)| Robot {
-            pos: pos[2..].parse::<Pos>().expect("invalid pos"),
-            step: step[2..].parse::<Step>().expect("invalid step"),
+// n² times the variance of one coordinate, kept in integers
+fn spread(values: impl Iterator<Item = i64>) -> i64 {
+    let (mut n, mut sum, mut squares) = (0, 0, 0);
+    for v in values {
+        n += 1;
+        sum += v;
+        squares += v * v;
+    }
+    n * squares - sum * sum
+}
+
+// the time step in 0..period where one coordinate is the most bunched up;
+// it repeats every `period` steps
+fn tightest(robots: &[Robot], period: u32, coord: impl Fn(&Robot) -> (i32, i32)) -> u32 {
+    let period_i = period as i64;
+    (0..period)
+        .min_by_key(|&t| {
+            spread(robots.iter().map(|robot| {
+                let (pos, step) = coord(robot);
+                (pos as i64 + step as i64 * t as i64).rem_euclid(period_i)
+            }))
         })
-        .collect();
+        .unwrap_or(0)
+}
 
-    let mut history = HashSet::new();
+// the smallest t >= 0 with t = a (mod m) and t = b (mod n), if any
+fn crt(a: u32, m: u32, b: u32, n: u32) -> Option<u32> {
+    let (a, m, b, n) = (a as i64, m as i64, b as i64, n as i64);
+    let (g, x, _) = extended_gcd(m, n);
+    if (b - a) % g != 0 {
+        return None;
+    }
+    let lcm = m / g * n;
+    let k = ((b - a) / g * x).rem_euclid(n / g);
+    Some((a + m * k).rem_euclid(lcm) as u32)
+}
 
-    // simulate robots
-    for i in 0.. {
-        for robot in &mut robots {
-            robot.simulate(1, width, height);
-        }
+// (g, x, y) with a * x + b * y = g
+fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - a / b * y)
+}
 
-        if !history.insert(robots.clone()) {
-            break;
-        }
+/// The first time step at which the robots draw the Easter egg.
+///
+/// The picture packs most robots into a small area. The x positions repeat
+/// every `width` steps and the y positions every `height` steps, so the
+/// steps where each axis has the least variance are found separately and
+/// joined with the Chinese Remainder Theorem. When the periods share a
+/// factor those steps may never line up, and there is no answer.
+pub fn easter_egg(input: &str, width: u32, height: u32) -> Option<u32> {
+    let robots = parse_robots(input);
+    let tx = tightest(&robots, width, |r| (r.pos.x, r.step.x));
+    let ty = tightest(&robots, height, |r| (r.pos.y, r.step.y));
+    crt(tx, width, ty, height)
+}
 
-        let map =
-            robots
-                .iter()
-                .map(|robot| robot.pos.clone())
-                .fold(HashMap::new(), |mut acc, pos| {
-                    *acc.entry(pos).or_insert(0) += 1;
-                    acc
-                });
-
-        let mut save = false;
-        let mut contents = String::new();
-
-        for y in 0..height {
-            let mut counter = 0;
-            for x in 0..width {
-                let pos = Pos {
-                    x: x as i32,
-                    y: y as i32,
-                };
-                let char = match map.get(&pos) {
-                    Some(count) => {
-                        counter += 1;
-                        assert!(*count <= 9);
-                        count.to_string().chars().next().unwrap()
-                    }
-                    None => {
-                        counter = 0;
-                        '.'
-                    }
-                };
-                contents.push(char);
-                if counter >= 3 {
-                    save = true;
-                }
-            }
-            contents.push('\n');
-        }
+/// Draws the robots after `seconds` steps, with the number of robots on
+/// each tile or `.` for empty tiles.
+pub fn render(input: &str, width: u32, height: u32, seconds: u32) -> String {
+    let mut robots = parse_robots(input);
+    let mut counts = HashMap::new();
+    for robot in &mut robots {
+        // a full lap on either axis changes nothing, so skip those
+        robot.simulate(seconds % (width * height), width, height);
+        *counts.entry(robot.pos).or_insert(0) += 1;
+    }
 
-        if save {
-            let filename: String = format!("output/{:?}.txt", i + 1);
-            let mut file = File::create(filename).expect("could not create file");
-            file.write_all(contents.as_bytes()).expect("could not write file");
+    let mut frame = String::new();
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            frame.push(match counts.get(&Pos { x, y }) {
+                Some(&count) => char::from_digit(count.min(9), 10).unwrap(),
+                None => '.',
+            });
         }
+        frame.push('\n');
     }
+    frame
+}
 
-    0
+pub fn part2(input: &str, width: u32, height: u32) -> Option<u32> {
+    easter_egg(input, width, height)
 }
@@ -1,9 +1,14 @@
-use day14::{part1, part2};
+use day14::{part1, part2, render};
+#[cfg(test)]
+use day14::easter_egg;
 use std::fs;
 
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let file_path = std::env::args().nth(1).ok_or("Usage: day1 <file_to_txt>")?;
+    let file_path = std::env::args()
+        .nth(1)
+        .ok_or("Usage: day14 <file_to_txt> [--render]")?;
+    let show = std::env::args().skip(2).any(|arg| arg == "--render");
     let contents = fs::read_to_string(&file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
@@ -12,9 +17,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("A1: {}", num);
 
     println!("Q2: What is the fewest number of seconds that must elapse for the robots to display the Easter egg?");
-    let num = part2(&contents, 101, 103);
+    let num = part2(&contents, 101, 103).ok_or("no step has the robots bunched up on both axes")?;
     println!("A2: {}", num);
 
+    if show {
+        print!("{}", render(&contents, 101, 103, num));
+    }
+
     Ok(())
 }
 
@@ -23,25 +32,51 @@ fn test_part_1() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part1(&contents, 11, 7);
     assert_eq!(num, 12);
 
-    return Ok(());
+    Ok(())
+}
+
+// robots that meet in a 3x3 square at `seconds`, spread out otherwise
+#[cfg(test)]
+fn picture(width: i32, height: i32, seconds: i32) -> String {
+    let mut input = String::new();
+    for i in 0..30 {
+        let (vx, vy) = (i * 7 % width - width / 2, i * 5 % height - height / 2);
+        let (x, y) = (4 + i % 3, 2 + i / 3 % 3);
+        let (px, py) = (
+            (x - vx * seconds).rem_euclid(width),
+            (y - vy * seconds).rem_euclid(height),
+        );
+        input.push_str(&format!("p={},{} v={},{}\n", px, py, vx, vy));
+    }
+    input
 }
 
-// #[test]
-// fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
-//     let file_path = "input/dummy.txt";
+#[test]
+fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
+    let contents = picture(11, 7, 40);
+    assert_eq!(easter_egg(&contents, 11, 7), Some(40));
+    assert_eq!(part2(&picture(101, 103, 7000), 101, 103), Some(7000));
 
-//     // Read file contents
-//     let contents = fs::read_to_string(&file_path)
-//         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+    // the robots meet on odd steps along x but stay together along y from
+    // the start, which a square grid can never reconcile
+    assert_eq!(easter_egg("p=0,0 v=1,0\np=2,0 v=-1,0\n", 4, 4), None);
 
-//     let num = part2(&contents, 11, 7);
-//     assert_eq!(num, 0);
+    let frame = render(&contents, 11, 7, 40);
+    assert_eq!(frame.lines().count(), 7);
+    assert!(frame.lines().all(|line| line.len() == 11));
+    let square: Vec<&str> = frame
+        .lines()
+        .skip(2)
+        .take(3)
+        .map(|row| &row[4..7])
+        .collect();
+    assert_eq!(square, vec!["444", "333", "333"]);
 
-//     return Ok(());
-// }
\ No newline at end of file
+    Ok(())
+}
@@ -1,95 +1,364 @@
-const MUL_START: &str = "mul(";
-const MUL_SEP: char = ',';
-const MUL_END: char = ')';
+use std::io::{self, Read};
 
-fn parse_mul(string: &str) -> Option<(i32, i32)> {
-    let mut parts = string.split(MUL_SEP);
+/// The sum of all `mul(a,b)` instructions in the corrupted memory, when
+/// `conditional` only those not switched off by a preceding `don't()`.
+pub fn parse_muls(string: &str, conditional: bool) -> i64 {
+    InstructionSet::day3(conditional).evaluate(string)
+}
+
+const ARG_START: char = '(';
+const ARG_SEP: char = ',';
+const ARG_END: char = ')';
+// an i64 is at most 19 digits and a sign
+const MAX_ARG_LEN: usize = 20;
+
+fn is_arg_char(c: char) -> bool {
+    c.is_ascii_digit() || c == '-' || c == '+' || c == ARG_SEP
+}
+
+/// What evaluating a single instruction does to the scanner state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    /// add the value to the running total (only counted while enabled)
+    Value(i64),
+    Enable,
+    Disable,
+    Nothing,
+}
+
+type Eval = Box<dyn Fn(&[i64]) -> Effect>;
 
-    let a = parts.next().map(|n| n.parse::<i32>())?;
-    let b = parts.next().map(|n| n.parse::<i32>())?;
-    if parts.next().is_some() {
-        return None;
+pub struct Instruction {
+    name: String,
+    arity: usize,
+    eval: Eval,
+}
+
+impl Instruction {
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
-    if let (Ok(a), Ok(b)) = (a, b) {
-        Some((a, b))
-    } else {
-        None
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    // longest argument list that can still hold `arity` valid numbers
+    fn max_args_len(&self) -> usize {
+        self.arity * (MAX_ARG_LEN + 1)
+    }
+
+    // tries to match this instruction at the start of `string`, returns the
+    // arguments and the number of bytes consumed when it does
+    fn attempt(&self, string: &str) -> Attempt<Vec<i64>> {
+        let Some(rest) = string
+            .strip_prefix(self.name.as_str())
+            .and_then(|rest| rest.strip_prefix(ARG_START))
+        else {
+            // "name(" itself might be cut off by the end of the input
+            let prefix_len = self.name.len() + ARG_START.len_utf8();
+            let incomplete = string.len() < prefix_len
+                && format!("{}{}", self.name, ARG_START).starts_with(string);
+            return if incomplete {
+                Attempt::Incomplete
+            } else {
+                Attempt::Failed
+            };
+        };
+
+        let Some(end_idx) = rest.find(|c: char| !is_arg_char(c)) else {
+            return if rest.len() <= self.max_args_len() {
+                Attempt::Incomplete
+            } else {
+                Attempt::Failed
+            };
+        };
+        if !rest[end_idx..].starts_with(ARG_END) {
+            return Attempt::Failed;
+        }
+
+        let content = &rest[..end_idx];
+        let args = if content.is_empty() {
+            Vec::new()
+        } else {
+            match content
+                .split(ARG_SEP)
+                .map(|n| n.parse::<i64>().ok())
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(args) => args,
+                None => return Attempt::Failed,
+            }
+        };
+
+        if args.len() != self.arity {
+            return Attempt::Failed;
+        }
+
+        let len = string.len() - rest.len() + end_idx + ARG_END.len_utf8();
+        Attempt::Found(args, len)
     }
 }
 
-pub fn parse_muls(string: &str, conditional: bool) -> i32 {
-    let mut result = 0;
-    let mut remaining = string;
-    let mut enabled = true;
+// result of trying to match at a position, `Incomplete` means the input ended
+// before it could be decided
+enum Attempt<T> {
+    Found(T, usize),
+    Incomplete,
+    Failed,
+}
 
-    while let Some(idx) = remaining.find(MUL_START) {
-        // for the next "mul(" find if there are any do() or don't()'s
+/// An instruction found in the corrupted memory together with its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<'a> {
+    pub offset: usize,
+    pub name: &'a str,
+    pub args: Vec<i64>,
+    pub effect: Effect,
+}
+
+/// A registry of instructions of the form `name(a,b,...)` which can be picked
+/// out of arbitrary noise.
+#[derive(Default)]
+pub struct InstructionSet {
+    instructions: Vec<Instruction>,
+}
+
+impl InstructionSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The instructions of the puzzle: `mul(a,b)` and, when conditional, `do()`/`don't()`.
+    /// A `mul` whose product does not fit in an i64 is ignored.
+    pub fn day3(conditional: bool) -> Self {
+        let mut set = Self::new();
+        set.register("mul", 2, |args| {
+            args[0]
+                .checked_mul(args[1])
+                .map_or(Effect::Nothing, Effect::Value)
+        });
         if conditional {
-            let pre = &remaining[..idx];
-            let disable = pre.rfind("don't()");
-            let enable = pre.rfind("do()");
-
-            enabled = match (disable, enable) {
-                (Some(disable), Some(enable)) => disable < enable,
-                (Some(_), None) => false,
-                (None, Some(_)) => true,
-                _ => enabled,
-            };
+            set.register_toggle("do", "don't");
         }
+        set
+    }
 
-        // skip "mul(" in the remaing string
-        remaining = &remaining[idx + MUL_START.len()..];
+    pub fn register<F>(&mut self, name: &str, arity: usize, eval: F) -> &mut Self
+    where
+        F: Fn(&[i64]) -> Effect + 'static,
+    {
+        assert!(
+            !name.is_empty() && !name.contains([ARG_START, ARG_SEP, ARG_END]),
+            "invalid instruction name '{}'",
+            name
+        );
+
+        // re-registering a name replaces the earlier instruction
+        self.instructions
+            .retain(|instruction| instruction.name != name);
+        self.instructions.push(Instruction {
+            name: name.to_string(),
+            arity,
+            eval: Box::new(eval),
+        });
+        self
+    }
+
+    /// Registers a pair of zero-argument instructions which enable and disable the
+    /// instructions that follow them.
+    pub fn register_toggle(&mut self, on: &str, off: &str) -> &mut Self {
+        self.register(on, 0, |_| Effect::Enable);
+        self.register(off, 0, |_| Effect::Disable)
+    }
 
-        // then see if we are enabled or not
-        if conditional && !enabled {
-            continue;
+    pub fn instructions(&self) -> impl Iterator<Item = &Instruction> {
+        self.instructions.iter()
+    }
+
+    // tries to match any instruction starting exactly at the start of `string`
+    fn attempt<'a>(&'a self, string: &str) -> Attempt<(&'a Instruction, Vec<i64>)> {
+        let mut incomplete = false;
+        for instruction in &self.instructions {
+            match instruction.attempt(string) {
+                Attempt::Found(args, len) => return Attempt::Found((instruction, args), len),
+                Attempt::Incomplete => incomplete = true,
+                Attempt::Failed => {}
+            }
         }
 
-        // now we skip to the end of the mul operator ')'
-        let Some(end_idx) = remaining.find(MUL_END) else {
-            continue;
-        };
+        if incomplete {
+            Attempt::Incomplete
+        } else {
+            Attempt::Failed
+        }
+    }
+
+    // scans `string` and feeds every instruction found to `found`, returns the
+    // number of bytes processed; unless `at_end` is set, this stops in front of
+    // an instruction that might continue past the end of `string`
+    fn scan_with<'a, F>(&'a self, string: &str, at_end: bool, mut found: F) -> usize
+    where
+        F: FnMut(Match<'a>) -> bool,
+    {
+        let mut offset = 0;
+        while offset < string.len() {
+            let remaining = &string[offset..];
+            match self.attempt(remaining) {
+                Attempt::Found((instruction, args), len) => {
+                    let effect = (instruction.eval)(&args);
+                    let name = instruction.name.as_str();
+                    let found_match = Match {
+                        offset,
+                        name,
+                        args,
+                        effect,
+                    };
+                    offset += len;
+                    if !found(found_match) {
+                        break;
+                    }
+                    continue;
+                }
+                Attempt::Incomplete if !at_end => break,
+                _ => {}
+            }
 
-        // and see if the captured content matches our criterea
-        let content = &remaining[..end_idx];
-        if let Some((a, b)) = parse_mul(content) {
-            result += a * b; // update result
-            remaining = &remaining[end_idx + 1..]; // skip the found mul
+            // skip to the next character
+            offset += remaining.chars().next().map_or(1, char::len_utf8);
         }
+        offset
     }
 
-    result
+    /// Iterates over all valid instructions in `string` in the order they appear.
+    pub fn scan<'a>(&'a self, string: &'a str) -> impl Iterator<Item = Match<'a>> + 'a {
+        let mut offset = 0;
+        std::iter::from_fn(move || {
+            let start = offset;
+            let mut next = None;
+            offset += self.scan_with(&string[start..], true, |found| {
+                next = Some(found);
+                false
+            });
+            next.map(|found| Match {
+                offset: start + found.offset,
+                ..found
+            })
+        })
+    }
+
+    /// Sums the values of all instructions which are enabled at the point they occur,
+    /// saturating at the bounds of an i64.
+    pub fn evaluate(&self, string: &str) -> i64 {
+        let mut enabled = true;
+        self.scan(string)
+            .filter_map(|found| apply(&mut enabled, found.effect))
+            .fold(0, i64::saturating_add)
+    }
+
+    /// Streaming version of `evaluate`, see `StreamScanner`.
+    pub fn evaluate_reader<R: Read>(&self, reader: R) -> io::Result<i64> {
+        StreamScanner::new(self, reader).evaluate()
+    }
 }
 
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
 
-pub fn parse_muls2(string: &str, conditional: bool) -> i32 {
-    if !conditional {
-        return string.split("mul(")
-            .filter_map(|str| str.find(')').map(|idx| &str[..idx]))
-            .filter_map(parse_mul)
-            .fold(0, |x, (a, b)| x + (a * b));
+/// Evaluates instructions read from any `Read` in fixed size chunks, so the input
+/// never has to be in memory as a whole. Only the tail of a chunk that might be the
+/// start of an instruction is carried over to the next one.
+pub struct StreamScanner<'a, R> {
+    set: &'a InstructionSet,
+    reader: R,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    enabled: bool,
+    total: i64,
+}
+
+impl<'a, R: Read> StreamScanner<'a, R> {
+    pub fn new(set: &'a InstructionSet, reader: R) -> Self {
+        Self {
+            set,
+            reader,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            buffer: Vec::new(),
+            enabled: true,
+            total: 0,
+        }
     }
 
-    string.split(')')
-        .scan(true, |enabled, str| {
-            if str.ends_with("do(") {
-                *enabled = true;
-                return Some(None);
-            }
-            if str.ends_with("don't(") {
-                *enabled = false;
-                return Some(None);
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Reads the input until the end and returns the sum of all enabled instructions,
+    /// saturating at the bounds of an i64.
+    pub fn evaluate(mut self) -> io::Result<i64> {
+        let mut chunk = vec![0; self.chunk_size];
+        loop {
+            let read = match self.reader.read(&mut chunk) {
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let eof = read == 0;
+            self.buffer.extend_from_slice(&chunk[..read]);
+            self.process(eof);
+
+            if eof {
+                return Ok(self.total);
             }
-            if !(*enabled) {
-                return Some(None);
+        }
+    }
+
+    // scans as much of the buffer as can be decided and drops the processed bytes
+    fn process(&mut self, eof: bool) {
+        loop {
+            // bytes that are not valid utf-8 can never be part of an instruction
+            let (valid_len, invalid_len) = match std::str::from_utf8(&self.buffer) {
+                Ok(text) => (text.len(), None),
+                Err(e) => (e.valid_up_to(), e.error_len()),
+            };
+            let text = std::str::from_utf8(&self.buffer[..valid_len]).unwrap();
+
+            let (enabled, total) = (&mut self.enabled, &mut self.total);
+            let at_end = eof || invalid_len.is_some();
+            let processed = self.set.scan_with(text, at_end, |found| {
+                if let Some(value) = apply(enabled, found.effect) {
+                    *total = total.saturating_add(value);
+                }
+                true
+            });
+
+            match invalid_len {
+                Some(invalid_len) => {
+                    self.buffer.drain(..processed + invalid_len);
+                }
+                None => {
+                    // whatever remains is a possible instruction or a cut off character
+                    self.buffer.drain(..processed);
+                    return;
+                }
             }
-            str
-                .rfind("mul(")
-                .map(|idx| &str[idx + 4..])
-                .map(parse_mul)
-                .or(Some(None))
-        })
-        .flatten()
-        .fold(0, |x, (a, b)| x + (a * b))
-}
\ No newline at end of file
+        }
+    }
+}
+
+// updates the enabled state and returns the value to add, if any
+pub(crate) fn apply(enabled: &mut bool, effect: Effect) -> Option<i64> {
+    match effect {
+        Effect::Value(value) if *enabled => Some(value),
+        Effect::Enable => {
+            *enabled = true;
+            None
+        }
+        Effect::Disable => {
+            *enabled = false;
+            None
+        }
+        _ => None,
+    }
+}
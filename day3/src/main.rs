@@ -1,5 +1,6 @@
 use day3::parse_muls;
-use day3::parse_muls2;
+#[cfg(test)]
+use day3::{Effect, InstructionSet, StreamScanner};
 use std::fs;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -25,13 +26,13 @@ fn test_all_muls() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
-    let sum = parse_muls2(&contents, false);
+    let sum = parse_muls(&contents, false);
     assert_eq!(sum, 161);
 
-    return Ok(());
+    Ok(())
 }
 
 #[test]
@@ -39,11 +40,69 @@ fn test_enabled_muls() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy2.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
-    let sum = parse_muls2(&contents, true);
+    let sum = parse_muls(&contents, true);
     assert_eq!(sum, 48);
 
-    return Ok(());
+    Ok(())
+}
+
+#[test]
+fn test_instruction_set() -> Result<(), Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string("input/dummy.txt")?;
+    assert_eq!(InstructionSet::day3(false).evaluate(&contents), 161);
+
+    let contents = fs::read_to_string("input/dummy2.txt")?;
+    assert_eq!(InstructionSet::day3(true).evaluate(&contents), 48);
+
+    // products that overflow are dropped, sums saturate
+    let set = InstructionSet::day3(false);
+    assert_eq!(set.evaluate("mul(9999999999,9999999999)mul(2,3)"), 6);
+    assert_eq!(set.evaluate("mul(9223372036854775807,1)mul(1,1)"), i64::MAX);
+
+    Ok(())
+}
+
+#[test]
+fn test_custom_instructions() {
+    let mut set = InstructionSet::day3(false);
+    set.register("add", 2, |args| Effect::Value(args[0] + args[1]))
+        .register("neg", 1, |args| Effect::Value(-args[0]))
+        .register_toggle("on", "off");
+
+    let memory = "xadd(1,2)mul(3,4]neg(5)off()?add(100,100)%on()mul(2,2)neg(1,2)add(7)";
+    let names: Vec<_> = set.scan(memory).map(|found| found.name).collect();
+    assert_eq!(names, ["add", "neg", "off", "add", "on", "mul"]);
+    assert_eq!(set.evaluate(memory), 3 - 5 + 4);
+}
+
+#[test]
+fn test_stream_scanner() -> Result<(), Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string("input/dummy2.txt")?;
+    let set = InstructionSet::day3(true);
+
+    // every chunk size splits some instruction across a boundary
+    for chunk_size in 1..=contents.len() {
+        let sum = StreamScanner::new(&set, contents.as_bytes())
+            .with_chunk_size(chunk_size)
+            .evaluate()?;
+        assert_eq!(sum, 48, "chunk size {}", chunk_size);
+    }
+
+    // invalid utf-8 is skipped like any other noise
+    let memory = b"mul(2,\xff3)mul(\xe2\x82\xac)mul(4,5)\xe2\x82\xacmul(1,1)\xe2";
+    for chunk_size in 1..=memory.len() {
+        let sum = StreamScanner::new(&set, &memory[..])
+            .with_chunk_size(chunk_size)
+            .evaluate()?;
+        assert_eq!(sum, 21, "chunk size {}", chunk_size);
+    }
+
+    // the running total saturates like the one of `evaluate`
+    let memory = "mul(9223372036854775807,1)mul(1,1)";
+    assert_eq!(set.evaluate_reader(memory.as_bytes())?, i64::MAX);
+
+    Ok(())
 }
@@ -1,93 +1,338 @@
-pub fn part1(input: &str) -> usize {
-    let width = input.lines().next().unwrap().len();
-    let height = input.lines().count();
-    let lines: Vec<_> = input
-        .lines()
-        .map(|line| line.chars().collect::<Vec<_>>())
-        .collect();
-
-    let mut result = 0;
-
-    // horizontals
-    for line in input.lines() {
-        result += line.matches("XMAS").count();
-        result += line.matches("SAMX").count();
-    }
-
-    // verticals
-    for i in 0..width {
-        let mut str = String::new();
-        for j in 0..height {
-            str.push(lines[j][i]);
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Pos {
+    pub x: usize,
+    pub y: usize,
+}
+
+/// A rectangular grid of letters.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<char>,
+}
+
+impl Grid {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> Option<char> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
         }
-        result += str.matches("XMAS").count();
-        result += str.matches("SAMX").count();
-    }
-
-    // diagonals
-    let mut ctr = 0;
-    while ctr < 2 * height - 1 {
-        let mut str = String::new();
-        let mut str2 = String::new();
-        for i in 0..width {
-            for j in 0..height {
-                if i + j == ctr {
-                    str.push(lines[i][j]);
-                    str2.push(lines[j][i]);
-                }
+        Some(self.cells[y as usize * self.width + x as usize])
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| Pos { x, y }))
+    }
+}
+
+impl FromStr for Grid {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let width = s.lines().next().ok_or(ParseError {})?.chars().count();
+        let mut cells = Vec::new();
+        let mut height = 0;
+
+        for line in s.lines() {
+            let len = cells.len();
+            cells.extend(line.chars());
+            if cells.len() - len != width {
+                return Err(ParseError {});
             }
+            height += 1;
         }
-        result += str.matches("XMAS").count();
-        result += str.matches("SAMX").count();
 
-        if str2.len() != height {
-            result += str2.matches("XMAS").count();
-            result += str2.matches("SAMX").count();
+        Ok(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Direction {
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+    Up,
+    UpRight,
+}
+
+impl Direction {
+    pub fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::Right => (1, 0),
+            Direction::DownRight => (1, 1),
+            Direction::Down => (0, 1),
+            Direction::DownLeft => (-1, 1),
+            Direction::Left => (-1, 0),
+            Direction::UpLeft => (-1, -1),
+            Direction::Up => (0, -1),
+            Direction::UpRight => (1, -1),
         }
+    }
 
-        ctr += 1;
+    pub fn reverse(self) -> Direction {
+        match self {
+            Direction::Right => Direction::Left,
+            Direction::DownRight => Direction::UpLeft,
+            Direction::Down => Direction::Up,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::Left => Direction::Right,
+            Direction::UpLeft => Direction::DownRight,
+            Direction::Up => Direction::Down,
+            Direction::UpRight => Direction::DownLeft,
+        }
+    }
+
+    /// Left to right and top to bottom, plus their reversals if requested.
+    pub fn orthogonal(reversals: bool) -> Vec<Direction> {
+        with_reversals(&[Direction::Right, Direction::Down], reversals)
     }
 
+    /// Both diagonals read from left to right, plus their reversals if requested.
+    pub fn diagonal(reversals: bool) -> Vec<Direction> {
+        with_reversals(&[Direction::DownRight, Direction::UpRight], reversals)
+    }
+
+    pub fn all(reversals: bool) -> Vec<Direction> {
+        let mut directions = Direction::orthogonal(reversals);
+        directions.extend(Direction::diagonal(reversals));
+        directions
+    }
+}
+
+fn with_reversals(directions: &[Direction], reversals: bool) -> Vec<Direction> {
+    let mut result = directions.to_vec();
+    if reversals {
+        result.extend(directions.iter().map(|direction| direction.reverse()));
+    }
     result
 }
 
-pub fn part2(input: &str) -> usize {
-    let width = input.lines().next().unwrap().len();
-    let height = input.lines().count();
-
-    let chars: Vec<_> = input.lines().flat_map(|l| l.chars().map(|c| c)).collect();
-    chars
-        .iter()
-        .enumerate()
-        .filter(|(_, c)| **c == 'A')
-        .map(|(idx, _)| idx)
-        .filter(|idx| {
-            idx % width > 0
-                && idx % width < width - 1
-                && *idx > width + 1
-                && *idx < width * (height - 1) + 1
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct WordMatch<'a> {
+    pub word: &'a str,
+    pub start: Pos,
+    pub direction: Direction,
+}
+
+/// Searches a grid for a set of words along a set of directions.
+#[derive(Debug, Clone)]
+pub struct WordSearch {
+    words: Vec<String>,
+    directions: Vec<Direction>,
+}
+
+impl WordSearch {
+    /// Searches for `words` in all eight directions.
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        WordSearch {
+            words: words.into_iter().map(Into::into).collect(),
+            directions: Direction::all(true),
+        }
+    }
+
+    pub fn with_directions(mut self, directions: &[Direction]) -> Self {
+        self.directions = directions.to_vec();
+        self
+    }
+
+    fn matches_at(grid: &Grid, word: &str, start: Pos, direction: Direction) -> bool {
+        let (dx, dy) = direction.delta();
+        word.chars().enumerate().all(|(i, c)| {
+            let x = start.x as isize + dx * i as isize;
+            let y = start.y as isize + dy * i as isize;
+            grid.get(x, y) == Some(c)
+        })
+    }
+
+    /// Every occurrence of every word, ordered by start position.
+    pub fn find(&self, grid: &Grid) -> Vec<WordMatch<'_>> {
+        let mut result = Vec::new();
+        for start in grid.positions() {
+            for word in &self.words {
+                if word.is_empty() {
+                    continue;
+                }
+                for &direction in &self.directions {
+                    if Self::matches_at(grid, word, start, direction) {
+                        result.push(WordMatch {
+                            word,
+                            start,
+                            direction,
+                        });
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+pub fn part1(input: &str) -> usize {
+    let grid = input.parse::<Grid>().expect("invalid grid");
+    WordSearch::new(["XMAS"]).find(&grid).len()
+}
+
+/// Cells of a stencil that match any letter.
+pub const WILDCARD: char = '.';
+
+/// A small 2D template of letters and wildcards.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Stencil {
+    width: usize,
+    height: usize,
+    cells: Vec<Option<char>>,
+}
+
+impl Stencil {
+    fn get(&self, x: usize, y: usize) -> Option<char> {
+        self.cells[y * self.width + x]
+    }
+
+    fn from_fn<F>(width: usize, height: usize, f: F) -> Stencil
+    where
+        F: Fn(usize, usize) -> Option<char>,
+    {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| f(x, y))
+            .collect();
+        Stencil {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Rotated by 90 degrees clockwise.
+    pub fn rotate(&self) -> Stencil {
+        Stencil::from_fn(self.height, self.width, |x, y| {
+            self.get(y, self.height - 1 - x)
         })
-        .filter(|idx| {
-            let idx = *idx;
-
-            let top_left = chars[idx - width - 1];
-            let top_right = chars[idx - width + 1];
-            let bottom_left = chars[idx + width - 1];
-            let bottom_right = chars[idx + width + 1];
-
-            let m1 = match (top_left, bottom_right) {
-                ('M', 'S') => true,
-                ('S', 'M') => true,
-                _ => false,
-            };
-
-            let m2 = match (top_right, bottom_left) {
-                ('M', 'S') => true,
-                ('S', 'M') => true,
-                _ => false,
-            };
-
-            m1 && m2
+    }
+
+    /// Mirrored left to right.
+    pub fn reflect(&self) -> Stencil {
+        Stencil::from_fn(self.width, self.height, |x, y| {
+            self.get(self.width - 1 - x, y)
+        })
+    }
+
+    /// All distinct rotations and reflections, starting with the stencil itself.
+    pub fn symmetries(&self) -> Vec<Stencil> {
+        let mut result: Vec<Stencil> = Vec::new();
+        for start in [self.clone(), self.reflect()] {
+            let mut stencil = start;
+            for _ in 0..4 {
+                let next = stencil.rotate();
+                if !result.contains(&stencil) {
+                    result.push(stencil);
+                }
+                stencil = next;
+            }
+        }
+        result
+    }
+
+    /// Whether the stencil lies within the grid at `pos` and every letter matches.
+    pub fn matches_at(&self, grid: &Grid, pos: Pos) -> bool {
+        if pos.x + self.width > grid.width || pos.y + self.height > grid.height {
+            return false;
+        }
+        (0..self.height).all(|y| {
+            (0..self.width).all(|x| match self.get(x, y) {
+                None => true,
+                c => grid.get((pos.x + x) as isize, (pos.y + y) as isize) == c,
+            })
         })
-        .count()
+    }
+}
+
+impl FromStr for Stencil {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let grid = s.parse::<Grid>()?;
+        Ok(Stencil {
+            width: grid.width,
+            height: grid.height,
+            cells: grid
+                .cells
+                .into_iter()
+                .map(|c| if c == WILDCARD { None } else { Some(c) })
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct StencilMatch<'a> {
+    /// top left corner of the stencil in the grid
+    pub pos: Pos,
+    pub stencil: &'a Stencil,
+}
+
+/// Searches a grid for a stencil, optionally in all its orientations.
+#[derive(Debug, Clone)]
+pub struct StencilSearch {
+    stencils: Vec<Stencil>,
+}
+
+impl StencilSearch {
+    pub fn new(stencil: Stencil) -> Self {
+        StencilSearch {
+            stencils: vec![stencil],
+        }
+    }
+
+    pub fn with_symmetries(self) -> Self {
+        StencilSearch {
+            stencils: self.stencils[0].symmetries(),
+        }
+    }
+
+    /// Every position where one of the stencils matches, ordered by position.
+    pub fn find(&self, grid: &Grid) -> Vec<StencilMatch<'_>> {
+        let mut result = Vec::new();
+        for pos in grid.positions() {
+            for stencil in &self.stencils {
+                if stencil.matches_at(grid, pos) {
+                    result.push(StencilMatch { pos, stencil });
+                }
+            }
+        }
+        result
+    }
+}
+
+pub fn part2(input: &str) -> usize {
+    let grid = input.parse::<Grid>().expect("invalid grid");
+    let x_mas = "M.S\n.A.\nM.S".parse::<Stencil>().unwrap();
+    StencilSearch::new(x_mas)
+        .with_symmetries()
+        .find(&grid)
+        .len()
 }
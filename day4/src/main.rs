@@ -1,4 +1,6 @@
 use day4::{part1, part2};
+#[cfg(test)]
+use day4::{Direction, Grid, Pos, Stencil, StencilSearch, WordSearch};
 use std::fs;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -21,13 +23,13 @@ fn test_part_1() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part1(&contents);
     assert_eq!(num, 18);
 
-    return Ok(());
+    Ok(())
 }
 
 #[test]
@@ -35,11 +37,88 @@ fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part2(&contents);
     assert_eq!(num, 9);
 
-    return Ok(());
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[test]
+fn test_word_search() -> Result<(), Box<dyn std::error::Error>> {
+    let grid: Grid = "CATX\nAXXX\nTXCX".parse().map_err(|_| "invalid grid")?;
+
+    let search = WordSearch::new(["CAT"]).with_directions(&Direction::all(false));
+    let found: Vec<_> = search
+        .find(&grid)
+        .iter()
+        .map(|m| (m.start, m.direction))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (Pos { x: 0, y: 0 }, Direction::Right),
+            (Pos { x: 0, y: 0 }, Direction::Down),
+        ]
+    );
+
+    let search = WordSearch::new(["TAC"]);
+    let found: Vec<_> = search
+        .find(&grid)
+        .iter()
+        .map(|m| (m.start, m.direction))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (Pos { x: 2, y: 0 }, Direction::Left),
+            (Pos { x: 0, y: 2 }, Direction::Up),
+        ]
+    );
+
+    let search = search.with_directions(&Direction::orthogonal(false));
+    assert!(search.find(&grid).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_stencil_search() -> Result<(), Box<dyn std::error::Error>> {
+    let grid: Grid = "XAXXX\nAAAXB\nXAXBB\nXXXXB"
+        .parse()
+        .map_err(|_| "invalid grid")?;
+
+    let plus: Stencil = ".A.\nAAA\n.A.".parse().map_err(|_| "invalid stencil")?;
+    assert_eq!(plus.symmetries().len(), 1);
+    let found: Vec<_> = StencilSearch::new(plus)
+        .find(&grid)
+        .iter()
+        .map(|m| m.pos)
+        .collect();
+    assert_eq!(found, [Pos { x: 0, y: 0 }]);
+
+    let corner: Stencil = ".B\nBB".parse().map_err(|_| "invalid stencil")?;
+    assert_eq!(corner.symmetries().len(), 4);
+    let found: Vec<_> = StencilSearch::new(corner.clone())
+        .with_symmetries()
+        .find(&grid)
+        .iter()
+        .map(|m| (m.pos, m.stencil == &corner))
+        .collect();
+    assert_eq!(
+        found,
+        [(Pos { x: 3, y: 1 }, true), (Pos { x: 3, y: 2 }, false)]
+    );
+
+    // wildcards don't let a stencil hang off the edge of the grid
+    let grid: Grid = "AB\nCD".parse().map_err(|_| "invalid grid")?;
+    let stencil: Stencil = "D.\n..".parse().map_err(|_| "invalid stencil")?;
+    assert!(!stencil.matches_at(&grid, Pos { x: 1, y: 1 }));
+    assert!(StencilSearch::new(stencil).find(&grid).is_empty());
+    let stencil: Stencil = "A.\n.D".parse().map_err(|_| "invalid stencil")?;
+    assert!(stencil.matches_at(&grid, Pos { x: 0, y: 0 }));
+
+    Ok(())
+}
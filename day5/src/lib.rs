@@ -1,98 +1,410 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
 
-struct State<'a> {
-    updates: Vec<&'a str>,
-    transitions: HashMap<usize, HashSet<usize>>
+#[derive(Debug, PartialEq, Eq)]
+pub enum OrderError {
+    /// pages which have to come before each other in a cycle, each page has to be
+    /// printed before the next one and the last one before the first
+    Cycle(Vec<u32>),
+    /// two pages for which the rules don't decide which has to come first
+    Ambiguous(u32, u32),
 }
 
-impl State<'_> {
-    fn is_valid_update(&self, chain: &Vec<usize>) -> bool {
-        for idx in 0..chain.len() - 1 {
-            let head = chain[idx];
-            let tail = &chain[idx + 1..];
+/// A broken rule `before|after`: `before` is at `index_before` in the update, which
+/// is after `after` at `index_after`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Violation {
+    pub before: u32,
+    pub after: u32,
+    pub index_before: usize,
+    pub index_after: usize,
+}
 
-            // check if there isn't any unallowed transition
-            let Some(err_transition) = self.transitions.get(&head) else {
-                continue;
-            };
-            let valid = !tail.iter().any(|item| err_transition.contains(item));
+/// Page ordering rules, `X|Y` means page X has to be printed before page Y.
+#[derive(Debug, Default, Clone)]
+pub struct RuleSet {
+    successors: HashMap<u32, HashSet<u32>>,
+}
+
+impl RuleSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_rule(&mut self, before: u32, after: u32) {
+        self.successors.entry(before).or_default().insert(after);
+    }
 
-            if !valid {
+    /// Whether there is a rule that `before` has to be printed before `after`.
+    pub fn must_precede(&self, before: u32, after: u32) -> bool {
+        self.successors
+            .get(&before)
+            .map(|successors| successors.contains(&after))
+            .unwrap_or(false)
+    }
+
+    pub fn is_valid_update(&self, update: &[u32]) -> bool {
+        for idx in 0..update.len().saturating_sub(1) {
+            let head = update[idx];
+            let tail = &update[idx + 1..];
+
+            // check if there isn't any unallowed transition
+            if tail.iter().any(|&item| self.must_precede(item, head)) {
                 return false;
             }
         }
 
-        return true;
+        true
     }
+
+    /// Orders the pages of an update such that all rules are satisfied, pages the rules
+    /// don't order keep their relative order from the update.
+    pub fn sort(&self, update: &[u32]) -> Result<Vec<u32>, OrderError> {
+        let order = self.topological_sort(update, false)?;
+        Ok(order.into_iter().map(|i| update[i]).collect())
+    }
+
+    /// Like `sort`, but fails if the rules allow more than one order.
+    pub fn order(&self, update: &[u32]) -> Result<Vec<u32>, OrderError> {
+        let order = self.topological_sort(update, true)?;
+        Ok(order.into_iter().map(|i| update[i]).collect())
+    }
+
+    /// Every rule broken by the update, ordered by the position of the page that
+    /// should have come first.
+    pub fn violations(&self, update: &[u32]) -> Vec<Violation> {
+        let mut result = Vec::new();
+        for (index_before, &before) in update.iter().enumerate() {
+            for (index_after, &after) in update[..index_before].iter().enumerate() {
+                if self.must_precede(before, after) {
+                    result.push(Violation {
+                        before,
+                        after,
+                        index_before,
+                        index_after,
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    /// The fewest pages that have to be moved to put the update in a valid order,
+    /// in the order they appear in the update.
+    ///
+    /// The pages that stay can't contain two pages in an order the rules, followed
+    /// transitively, forbid. That relation is itself transitive, so the most pages
+    /// that can stay are a maximum antichain of it.
+    pub fn minimal_fix(&self, update: &[u32]) -> Result<Vec<u32>, OrderError> {
+        self.topological_sort(update, false)?;
+        let reaches = self.closure(update);
+
+        // for every page, the later pages that have to come before it
+        let n = update.len();
+        let misplaced: Vec<Vec<usize>> = (0..n)
+            .map(|i| (i + 1..n).filter(|&j| reaches[j][i]).collect())
+            .collect();
+        let stay = max_antichain(&misplaced);
+        Ok((0..n).filter(|&i| !stay[i]).map(|i| update[i]).collect())
+    }
+
+    // reaches[i][j] is whether the rules between pages of the update, followed
+    // transitively, put page i before page j
+    fn closure(&self, update: &[u32]) -> Vec<Vec<bool>> {
+        let n = update.len();
+        let mut reaches = vec![vec![false; n]; n];
+        for (i, row) in reaches.iter_mut().enumerate() {
+            let mut stack = vec![i];
+            while let Some(k) = stack.pop() {
+                for j in 0..n {
+                    if !row[j] && self.must_precede(update[k], update[j]) {
+                        row[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+        reaches
+    }
+
+    // Kahn's algorithm on the rules restricted to the pages of the update, nodes are
+    // indices into the update and so is the result
+    fn topological_sort(&self, update: &[u32], unique: bool) -> Result<Vec<usize>, OrderError> {
+        let n = update.len();
+        let mut successors = vec![Vec::new(); n];
+        let mut in_degree = vec![0; n];
+        for (i, &before) in update.iter().enumerate() {
+            for (j, &after) in update.iter().enumerate() {
+                if i != j && self.must_precede(before, after) {
+                    successors[i].push(j);
+                    in_degree[j] += 1;
+                }
+            }
+        }
+
+        let mut ready: VecDeque<usize> = (0..n).filter(|&i| in_degree[i] == 0).collect();
+        let mut result = Vec::with_capacity(n);
+        while let Some(i) = ready.pop_front() {
+            if unique {
+                if let Some(&j) = ready.front() {
+                    return Err(OrderError::Ambiguous(update[i], update[j]));
+                }
+            }
+
+            result.push(i);
+            for &j in &successors[i] {
+                in_degree[j] -= 1;
+                if in_degree[j] == 0 {
+                    // keep the ready pages in update order
+                    let pos = ready.partition_point(|&k| k < j);
+                    ready.insert(pos, j);
+                }
+            }
+        }
+
+        if result.len() < n {
+            let cycle = find_cycle(update, &successors, &in_degree);
+            return Err(OrderError::Cycle(cycle));
+        }
+
+        Ok(result)
+    }
+}
+
+// the largest set of nodes no two of which are related by a transitive order,
+// given as the successors of every node; by König's theorem this is the set of
+// nodes reached on the left but not on the right by alternating paths from the
+// unmatched nodes of a maximum matching
+fn max_antichain(order: &[Vec<usize>]) -> Vec<bool> {
+    let n = order.len();
+    // the node on the left matched to each node on the right
+    let mut matched = vec![None; n];
+    for u in 0..n {
+        augment(order, u, &mut vec![false; n], &mut matched);
+    }
+
+    let mut left = vec![true; n];
+    for &u in matched.iter().flatten() {
+        left[u] = false;
+    }
+    let mut right = vec![false; n];
+    let mut stack: Vec<usize> = (0..n).filter(|&u| left[u]).collect();
+    while let Some(u) = stack.pop() {
+        for &v in &order[u] {
+            if right[v] {
+                continue;
+            }
+            right[v] = true;
+            if let Some(w) = matched[v] {
+                if !left[w] {
+                    left[w] = true;
+                    stack.push(w);
+                }
+            }
+        }
+    }
+
+    (0..n).map(|u| left[u] && !right[u]).collect()
 }
 
-fn parse(input: &str) -> State {
-    let mut iter = input.lines();
-    let rules: Vec<_> = iter.by_ref().take_while(|l| !l.is_empty()).collect();
+// looks for an augmenting path from `u`, Kuhn's algorithm
+fn augment(
+    order: &[Vec<usize>],
+    u: usize,
+    seen: &mut [bool],
+    matched: &mut [Option<usize>],
+) -> bool {
+    for &v in &order[u] {
+        if seen[v] {
+            continue;
+        }
+        seen[v] = true;
+        if matched[v].is_none_or(|w| augment(order, w, seen, matched)) {
+            matched[v] = Some(u);
+            return true;
+        }
+    }
+    false
+}
 
-    let mut transitions: HashMap<usize, HashSet<usize>> = HashMap::new();
-    for rule in rules {
-        let mut rule = rule.split('|');
-        let (before, after) = (
-            rule.next().unwrap().parse::<usize>().unwrap(),
-            rule.next().unwrap().parse::<usize>().unwrap(),
-        );
+// every node that is left with a positive in-degree has a predecessor that is also
+// left, so walking backwards along those must end up in a cycle
+fn find_cycle(update: &[u32], successors: &[Vec<usize>], in_degree: &[usize]) -> Vec<u32> {
+    let mut predecessor = vec![None; update.len()];
+    for (i, successors) in successors.iter().enumerate() {
+        for &j in successors {
+            if in_degree[i] > 0 && in_degree[j] > 0 {
+                predecessor[j] = Some(i);
+            }
+        }
+    }
 
-        let items = transitions.entry(after).or_default();
-        items.insert(before);
+    let mut visited = vec![false; update.len()];
+    let mut node = (0..update.len()).find(|&i| in_degree[i] > 0).unwrap();
+    while !visited[node] {
+        visited[node] = true;
+        node = predecessor[node].unwrap();
     }
 
-    State { updates: iter.collect(), transitions: transitions }
+    let mut cycle = vec![update[node]];
+    let mut current = predecessor[node].unwrap();
+    while current != node {
+        cycle.push(update[current]);
+        current = predecessor[current].unwrap();
+    }
+    cycle.reverse();
+    cycle
 }
 
-pub fn part1(input: &str) -> u64 {
+/// The puzzle input: page ordering rules followed by the updates.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PrintQueue {
+    pub rules: Vec<(u32, u32)>,
+    pub updates: Vec<Vec<u32>>,
+}
 
-    let state = parse(input);
-    let mut sum = 0;
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// a rule that isn't of the form `X|Y`
+    InvalidRule,
+    /// a rule `X|X`
+    SelfReferencingRule(u32),
+    /// a page number in an update that isn't a number
+    InvalidPage,
+    DuplicatePage(u32),
+    /// an update with an even number of pages has no middle page
+    EvenLength(usize),
+}
 
-    for update in &state.updates {
-        let chain: Vec<_> = update
-            .split(",")
-            .map(|x| x.parse::<usize>().unwrap())
-            .collect();
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line number in the input
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
 
-            if state.is_valid_update(&chain) {
-                let mid_idx: usize = chain.len() / 2;
-                sum += chain[mid_idx] as u64;
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match self.kind {
+            ParseErrorKind::InvalidRule => write!(f, "expected a rule \"X|Y\""),
+            ParseErrorKind::SelfReferencingRule(page) => {
+                write!(f, "rule {}|{} references itself", page, page)
+            }
+            ParseErrorKind::InvalidPage => write!(f, "expected comma separated page numbers"),
+            ParseErrorKind::DuplicatePage(page) => write!(f, "page {} occurs twice", page),
+            ParseErrorKind::EvenLength(len) => {
+                write!(f, "update of {} pages has no middle page", len)
             }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn parse_rule(line: &str) -> Result<(u32, u32), ParseErrorKind> {
+    let (before, after) = line.split_once('|').ok_or(ParseErrorKind::InvalidRule)?;
+    let before = before
+        .parse::<u32>()
+        .map_err(|_| ParseErrorKind::InvalidRule)?;
+    let after = after
+        .parse::<u32>()
+        .map_err(|_| ParseErrorKind::InvalidRule)?;
+
+    if before == after {
+        return Err(ParseErrorKind::SelfReferencingRule(before));
+    }
+
+    Ok((before, after))
+}
+
+fn parse_update(line: &str) -> Result<Vec<u32>, ParseErrorKind> {
+    let update = line
+        .split(',')
+        .map(|page| page.parse::<u32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ParseErrorKind::InvalidPage)?;
+
+    let mut seen = HashSet::new();
+    if let Some(&page) = update.iter().find(|&&page| !seen.insert(page)) {
+        return Err(ParseErrorKind::DuplicatePage(page));
+    }
+
+    if update.len() % 2 == 0 {
+        return Err(ParseErrorKind::EvenLength(update.len()));
+    }
+
+    Ok(update)
+}
+
+impl FromStr for PrintQueue {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate().map(|(idx, line)| (idx + 1, line));
+        let error = |line, kind| ParseError { line, kind };
+
+        let mut rules = Vec::new();
+        for (line, rule) in lines.by_ref().take_while(|(_, l)| !l.is_empty()) {
+            rules.push(parse_rule(rule).map_err(|kind| error(line, kind))?);
+        }
+
+        let mut updates = Vec::new();
+        for (line, update) in lines {
+            updates.push(parse_update(update).map_err(|kind| error(line, kind))?);
+        }
+
+        Ok(PrintQueue { rules, updates })
+    }
+}
+
+impl PrintQueue {
+    pub fn rule_set(&self) -> RuleSet {
+        let mut rule_set = RuleSet::new();
+        for &(before, after) in &self.rules {
+            rule_set.add_rule(before, after);
+        }
+        rule_set
+    }
+}
+
+fn parse(input: &str) -> PrintQueue {
+    match input.parse() {
+        Ok(queue) => queue,
+        Err(e) => panic!("invalid input, {e}"),
+    }
+}
+
+pub fn part1(input: &str) -> u64 {
+    let queue = parse(input);
+    let rules = queue.rule_set();
+    let mut sum = 0;
+
+    for chain in &queue.updates {
+        if rules.is_valid_update(chain) {
+            let mid_idx: usize = chain.len() / 2;
+            sum += chain[mid_idx] as u64;
+        }
     }
 
     sum
 }
 
 pub fn part2(input: &str) -> u64 {
-    let state = parse(input);
+    let queue = parse(input);
+    let rules = queue.rule_set();
     let mut sum = 0;
 
-    for update in &state.updates {
-        let chain: Vec<_> = update
-            .split(",")
-            .map(|x| x.parse::<usize>().unwrap())
-            .collect();
-
-        if state.is_valid_update(&chain) {
+    for chain in &queue.updates {
+        if rules.is_valid_update(chain) {
             continue;
         }
 
         // let's fix the chain
-        let mut new_chain = chain.clone();
-        for item in &chain {
-            let count = chain
-                .iter()
-                .filter(|i| {
-                    state.transitions
-                        .get(i)
-                        .map(|t| t.contains(item))
-                        .unwrap_or(false)
-                })
-                .count();
-            new_chain[count] = *item;
-        }
+        let new_chain = match rules.order(chain) {
+            Ok(new_chain) => new_chain,
+            Err(e) => panic!("can't order update {chain:?}: {e:?}"),
+        };
 
         let mid_idx: usize = new_chain.len() / 2;
         sum += new_chain[mid_idx] as u64;
@@ -100,3 +412,34 @@ pub fn part2(input: &str) -> u64 {
 
     sum
 }
+
+/// Why an update is in the wrong order.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub update: Vec<u32>,
+    pub violations: Vec<Violation>,
+    pub minimal_fix: Result<Vec<u32>, OrderError>,
+}
+
+pub fn explain(input: &str) -> Vec<Explanation> {
+    let queue = parse(input);
+    let rules = queue.rule_set();
+
+    queue
+        .updates
+        .into_iter()
+        .filter_map(|update| {
+            let violations = rules.violations(&update);
+            if violations.is_empty() {
+                return None;
+            }
+
+            let minimal_fix = rules.minimal_fix(&update);
+            Some(Explanation {
+                update,
+                violations,
+                minimal_fix,
+            })
+        })
+        .collect()
+}
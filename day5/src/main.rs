@@ -1,8 +1,16 @@
-use day5::{part1, part2};
+use day5::{explain, part1, part2};
+#[cfg(test)]
+use day5::{OrderError, ParseError, ParseErrorKind, PrintQueue, RuleSet, Violation};
 use std::fs;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let file_path = std::env::args().nth(1).ok_or("Usage: day1 <file_to_txt>")?;
+    let (flags, paths): (Vec<_>, Vec<_>) = std::env::args()
+        .skip(1)
+        .partition(|arg| arg.starts_with("--"));
+    let file_path = paths
+        .into_iter()
+        .next()
+        .ok_or("Usage: day5 <file_to_txt> [--explain]")?;
     let contents = fs::read_to_string(&file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
@@ -13,21 +21,48 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Q2: What do you get if you add up the middle page numbers after correctly ordering just those updates?");
     let num = part2(&contents);
     println!("A2: {}", num);
+
+    if flags.iter().any(|flag| flag == "--explain") {
+        print_explanations(&contents);
+    }
     Ok(())
 }
 
+fn print_explanations(contents: &str) {
+    for explanation in explain(contents) {
+        let update: Vec<_> = explanation.update.iter().map(|x| x.to_string()).collect();
+        println!();
+        println!("Update {} is invalid:", update.join(","));
+
+        for v in &explanation.violations {
+            println!(
+                "  rule {}|{} is broken: {} is at index {}, after {} at index {}",
+                v.before, v.after, v.before, v.index_before, v.after, v.index_after
+            );
+        }
+
+        match &explanation.minimal_fix {
+            Ok(pages) => {
+                let pages: Vec<_> = pages.iter().map(|x| x.to_string()).collect();
+                println!("  fewest pages to move: {}", pages.join(","));
+            }
+            Err(e) => println!("  can't be fixed: {:?}", e),
+        }
+    }
+}
+
 #[test]
 fn test_part_1() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part1(&contents);
     assert_eq!(num, 143);
 
-    return Ok(());
+    Ok(())
 }
 
 #[test]
@@ -35,11 +70,169 @@ fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part2(&contents);
     assert_eq!(num, 123);
 
-    return Ok(());
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[test]
+fn test_rule_set_order() {
+    let mut rules = RuleSet::new();
+    for (before, after) in [(1, 2), (2, 3), (1, 3), (3, 4)] {
+        rules.add_rule(before, after);
+    }
+
+    assert_eq!(rules.order(&[4, 2, 3, 1]), Ok(vec![1, 2, 3, 4]));
+    assert_eq!(rules.order(&[4, 5, 3]), Err(OrderError::Ambiguous(5, 3)));
+    assert_eq!(rules.sort(&[4, 5, 3]), Ok(vec![5, 3, 4]));
+
+    rules.add_rule(4, 2);
+    assert_eq!(
+        rules.order(&[4, 2, 3, 1]),
+        Err(OrderError::Cycle(vec![2, 3, 4]))
+    );
+}
+
+#[test]
+fn test_explain() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "input/dummy.txt";
+
+    // Read file contents
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+
+    let explanations = explain(&contents);
+    let updates: Vec<_> = explanations.iter().map(|e| e.update.clone()).collect();
+    assert_eq!(
+        updates,
+        [
+            vec![75, 97, 47, 61, 53],
+            vec![61, 13, 29],
+            vec![97, 13, 75, 29, 47]
+        ]
+    );
+
+    assert_eq!(
+        explanations[0].violations,
+        [Violation {
+            before: 97,
+            after: 75,
+            index_before: 1,
+            index_after: 0
+        }]
+    );
+    assert_eq!(explanations[0].minimal_fix, Ok(vec![75]));
+    assert_eq!(explanations[1].minimal_fix, Ok(vec![13]));
+    assert_eq!(explanations[2].violations.len(), 4);
+    assert_eq!(explanations[2].minimal_fix, Ok(vec![13, 29]));
+
+    Ok(())
+}
+
+#[test]
+fn test_minimal_fix() {
+    // every order of the pages, by inserting the last page anywhere
+    fn orders(pages: &[u32]) -> Vec<Vec<u32>> {
+        let Some((&last, rest)) = pages.split_last() else {
+            return vec![vec![]];
+        };
+        orders(rest)
+            .into_iter()
+            .flat_map(|order| {
+                (0..=order.len()).map(move |i| {
+                    let mut order = order.clone();
+                    order.insert(i, last);
+                    order
+                })
+            })
+            .collect()
+    }
+
+    // whether `pages` appear in `order` in the same relative order
+    fn keeps(order: &[u32], pages: &[u32]) -> bool {
+        let mut order = order.iter();
+        pages.iter().all(|page| order.any(|p| p == page))
+    }
+
+    let all = orders(&[1, 2, 3, 4, 5, 6]);
+    let mut seed: u64 = 0x2545f491;
+    let mut random = |n: u64| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % n) as usize
+    };
+
+    for _ in 0..300 {
+        // rules only point from a lower to a higher rank, so there are no cycles
+        let rank = &all[random(720)];
+        let mut rules = RuleSet::new();
+        for i in 0..6 {
+            for j in i + 1..6 {
+                if random(3) == 0 {
+                    rules.add_rule(rank[i], rank[j]);
+                }
+            }
+        }
+        let update = &all[random(720)][..2 + random(5)];
+
+        // brute force: the most pages of the update any valid order keeps in place
+        let valid: Vec<_> = orders(update)
+            .into_iter()
+            .filter(|order| rules.is_valid_update(order))
+            .collect();
+        let most = (0..1u32 << update.len())
+            .map(|mask| {
+                let pages: Vec<_> = (0..update.len())
+                    .filter(|i| mask & (1 << i) != 0)
+                    .map(|i| update[i])
+                    .collect();
+                pages
+            })
+            .filter(|pages| valid.iter().any(|order| keeps(order, pages)))
+            .map(|pages| pages.len())
+            .max();
+
+        let fix = rules.minimal_fix(update).unwrap();
+        let stay: Vec<_> = update
+            .iter()
+            .copied()
+            .filter(|page| !fix.contains(page))
+            .collect();
+        assert_eq!(Some(stay.len()), most, "update {:?}", update);
+        assert!(valid.iter().any(|order| keeps(order, &stay)));
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    let parse = |input: &str| input.parse::<PrintQueue>();
+
+    assert_eq!(
+        parse("1|2\n2|3\n\n1,2,3\n3"),
+        Ok(PrintQueue {
+            rules: vec![(1, 2), (2, 3)],
+            updates: vec![vec![1, 2, 3], vec![3]],
+        })
+    );
+
+    let error = |line, kind| Err(ParseError { line, kind });
+    assert_eq!(
+        parse("1|2\n2-3\n\n1"),
+        error(2, ParseErrorKind::InvalidRule)
+    );
+    assert_eq!(
+        parse("1|2\n4|4\n\n1"),
+        error(2, ParseErrorKind::SelfReferencingRule(4))
+    );
+    assert_eq!(parse("1|2\n\n1,x,3"), error(3, ParseErrorKind::InvalidPage));
+    assert_eq!(
+        parse("1|2\n\n1\n1,2,1"),
+        error(4, ParseErrorKind::DuplicatePage(1))
+    );
+    assert_eq!(parse("1|2\n\n1,2"), error(3, ParseErrorKind::EvenLength(2)));
+}
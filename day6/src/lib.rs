@@ -1,17 +1,18 @@
-use std::collections::HashSet;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
 #[derive(Debug, PartialEq, Eq)]
-struct Error;
+pub struct Error;
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
-struct Pos {
-    x: i32,
-    y: i32,
+pub struct Pos {
+    pub x: i32,
+    pub y: i32,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
-enum Direction {
+pub enum Direction {
     Up,
     Down,
     Left,
@@ -28,6 +29,28 @@ impl Direction {
         }
     }
 
+    fn turn_left(self) -> Direction {
+        self.turn_right().reverse()
+    }
+
+    fn reverse(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
+
     fn step(&self, pos: &mut Pos) {
         match self {
             Direction::Up => pos.y -= 1,
@@ -52,36 +75,90 @@ impl FromStr for Direction {
     }
 }
 
+/// What a guard does when it runs into an obstacle.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
+pub enum TurnPolicy {
+    #[default]
+    TurnRight,
+    TurnLeft,
+    Reverse,
+    /// turn right the first time, left the second time, and so on
+    Alternate,
+}
+
+impl TurnPolicy {
+    // the direction after turning with `phase` being the number of earlier turns
+    // modulo `phases`
+    fn turn(self, direction: Direction, phase: usize) -> Direction {
+        match (self, phase) {
+            (TurnPolicy::TurnRight, _) | (TurnPolicy::Alternate, 0) => direction.turn_right(),
+            (TurnPolicy::TurnLeft, _) | (TurnPolicy::Alternate, _) => direction.turn_left(),
+            (TurnPolicy::Reverse, _) => direction.reverse(),
+        }
+    }
+
+    // number of turns after which the policy repeats itself
+    fn phases(self) -> usize {
+        match self {
+            TurnPolicy::Alternate => 2,
+            _ => 1,
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 struct Guard {
     pos: Pos,
     direction: Direction,
 }
 
+impl Guard {
+    // every guard in the map, row by row
+    fn parse_all(s: &str) -> Vec<Guard> {
+        s.lines()
+            .enumerate()
+            .flat_map(|(y, line)| line.chars().enumerate().map(move |(x, chr)| (x, y, chr)))
+            .filter_map(|(x, y, c)| {
+                let direction = c.to_string().parse::<Direction>().ok()?;
+                Some(Guard {
+                    pos: Pos {
+                        x: x as i32,
+                        y: y as i32,
+                    },
+                    direction,
+                })
+            })
+            .collect()
+    }
+}
+
 impl FromStr for Guard {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let iter = s
-            .lines()
-            .enumerate()
-            .flat_map(|(y, line)| line.chars().enumerate().map(move |(x, chr)| (x, y, chr)));
+        Guard::parse_all(s).into_iter().next().ok_or(Error {})
+    }
+}
 
-        for (x, y, c) in iter {
-            let Ok(dir) = c.to_string().parse::<Direction>() else {
-                continue;
-            };
+// one bit per cell of the room, row by row
+#[derive(Debug, Clone)]
+struct Bitmap {
+    bits: Vec<u64>,
+}
 
-            return Ok(Guard {
-                pos: Pos {
-                    x: x as i32,
-                    y: y as i32,
-                },
-                direction: dir,
-            });
+impl Bitmap {
+    fn new(len: usize) -> Self {
+        Bitmap {
+            bits: vec![0; len.div_ceil(64)],
         }
+    }
 
-        Err(Error {})
+    fn get(&self, idx: usize) -> bool {
+        self.bits[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    fn set(&mut self, idx: usize) {
+        self.bits[idx / 64] |= 1 << (idx % 64);
     }
 }
 
@@ -89,123 +166,563 @@ impl FromStr for Guard {
 struct Room {
     width: usize,
     height: usize,
-    obstacles: HashSet<Pos>,
+    obstacles: Bitmap,
 }
 
 impl FromStr for Room {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let items: Vec<_> = s
-            .lines()
-            .enumerate()
-            .flat_map(|(y, line)| line.chars().enumerate().map(move |(x, chr)| (x, y, chr)))
-            .collect();
+        let width = s.lines().next().ok_or(Error {})?.len();
+        let height = s.lines().count();
+
+        let mut obstacles = Bitmap::new(width * height);
+        for (y, line) in s.lines().enumerate() {
+            if line.len() != width {
+                return Err(Error {});
+            }
+            for (x, _) in line.chars().enumerate().filter(|(_, c)| *c == '#') {
+                obstacles.set(y * width + x);
+            }
+        }
 
         Ok(Room {
-            width: *items.iter().map(|(x, _, _)| x).max().unwrap() + 1,
-            height: *items.iter().map(|(_, y, _)| y).max().unwrap() + 1,
-            obstacles: items
-                .iter()
-                .filter(|(_, _, c)| *c == '#')
-                .map(|(x, y, _)| Pos { x: *x as i32, y: *y as i32})
-                .collect(),
+            width,
+            height,
+            obstacles,
         })
     }
 }
 
 impl Room {
+    fn index(&self, pos: &Pos) -> usize {
+        pos.y as usize * self.width + pos.x as usize
+    }
+
     fn is_safe(&self, pos: &Pos) -> bool {
-        !self.obstacles.contains(pos)
+        !self.contains(pos) || !self.obstacles.get(self.index(pos))
     }
 
     fn contains(&self, pos: &Pos) -> bool {
         pos.x >= 0 && (pos.x as usize) < self.width && pos.y >= 0 && (pos.y as usize) < self.height
     }
-}
-
-pub fn part1(input: &str) -> u32 {
-    let room = input.parse::<Room>().unwrap();
-    let mut guard = input.parse::<Guard>().unwrap();
-
-    let mut visited = HashSet::new();
-    visited.insert(guard.pos);
 
-    loop {
+    // lets the guard take a single step, either moving forward or turning, returns
+    // false if the guard walks out of the room
+    fn step(
+        &self,
+        guard: &mut Guard,
+        phase: &mut usize,
+        policy: TurnPolicy,
+        obstacle: Option<Pos>,
+    ) -> bool {
         let mut next_pos = guard.pos;
         guard.direction.step(&mut next_pos);
 
-        if !room.is_safe(&next_pos) {
-            guard.direction = guard.direction.turn_right();
+        if !self.contains(&next_pos) {
+            return false;
+        }
+
+        if !self.is_safe(&next_pos) || Some(next_pos) == obstacle {
+            guard.direction = policy.turn(guard.direction, *phase);
+            *phase = (*phase + 1) % policy.phases();
         } else {
             guard.pos = next_pos;
         }
+        true
+    }
 
-        if !room.contains(&guard.pos) {
-            break;
-        }
+    // every state of the guard until it leaves the room or repeats a state
+    fn trajectory(
+        &self,
+        mut guard: Guard,
+        policy: TurnPolicy,
+        obstacle: Option<Pos>,
+    ) -> Trajectory {
+        let mut phase = 0;
+        let mut states = Vec::new();
+        let mut seen = HashMap::new();
 
-        visited.insert(guard.pos);
+        loop {
+            if let Some(&start) = seen.get(&(guard, phase)) {
+                let cycle = states.split_off(start);
+                return Trajectory::Loop {
+                    prefix: states,
+                    cycle,
+                };
+            }
+            seen.insert((guard, phase), states.len());
+            states.push((guard.pos, guard.direction));
+
+            if !self.step(&mut guard, &mut phase, policy, obstacle) {
+                return Trajectory::Exit(states);
+            }
+        }
     }
+}
+
+// marks leaving the room in the jump table
+const EXIT: u32 = u32::MAX;
 
-    visited.len() as u32
+// for every cell and direction, the cell where the guard has to turn next, or EXIT
+// when walking in that direction leaves the room
+struct JumpTable {
+    width: usize,
+    stops: [Vec<u32>; 4],
 }
 
-pub fn part2(input: &str) -> u32 {
-    let mut room = input.parse::<Room>().unwrap();
-    let initial_guard = input.parse::<Guard>().unwrap();
-    let mut guard = initial_guard;
+impl JumpTable {
+    fn new(room: &Room) -> Self {
+        let (width, height) = (room.width, room.height);
+        let blocked = |x: usize, y: usize| room.obstacles.get(y * width + x);
+        let mut stops = [(); 4].map(|_| vec![EXIT; width * height]);
+
+        // sweep every row and column starting at the edge the guard walks towards
+        for x in 0..width {
+            for y in 1..height {
+                let idx = y * width + x;
+                stops[Direction::Up.index()][idx] = if blocked(x, y - 1) {
+                    idx as u32
+                } else {
+                    stops[Direction::Up.index()][idx - width]
+                };
+            }
+            for y in (0..height - 1).rev() {
+                let idx = y * width + x;
+                stops[Direction::Down.index()][idx] = if blocked(x, y + 1) {
+                    idx as u32
+                } else {
+                    stops[Direction::Down.index()][idx + width]
+                };
+            }
+        }
+        for y in 0..height {
+            for x in 1..width {
+                let idx = y * width + x;
+                stops[Direction::Left.index()][idx] = if blocked(x - 1, y) {
+                    idx as u32
+                } else {
+                    stops[Direction::Left.index()][idx - 1]
+                };
+            }
+            for x in (0..width - 1).rev() {
+                let idx = y * width + x;
+                stops[Direction::Right.index()][idx] = if blocked(x + 1, y) {
+                    idx as u32
+                } else {
+                    stops[Direction::Right.index()][idx + 1]
+                };
+            }
+        }
 
-    let mut visited = HashSet::new();
-    loop {
-        let mut next_pos = guard.pos;
-        guard.direction.step(&mut next_pos);
+        JumpTable { width, stops }
+    }
 
-        if !room.is_safe(&next_pos) {
-            guard.direction = guard.direction.turn_right();
-        } else {
-            guard.pos = next_pos;
+    // the cell where a guard walking from `idx` has to turn when there is an extra
+    // obstacle at `obstacle`, None if it leaves the room
+    fn next_stop(&self, idx: usize, direction: Direction, obstacle: usize) -> Option<usize> {
+        let stop = self.stops[direction.index()][idx];
+        let (x, y) = (idx % self.width, idx / self.width);
+        let (ox, oy) = (obstacle % self.width, obstacle / self.width);
+
+        // number of cells until the extra obstacle, if it is ahead of the guard
+        let distance = match direction {
+            Direction::Up => (ox == x && oy < y).then(|| y - oy),
+            Direction::Down => (ox == x && oy > y).then(|| oy - y),
+            Direction::Left => (oy == y && ox < x).then(|| x - ox),
+            Direction::Right => (oy == y && ox > x).then(|| ox - x),
+        };
+        let reach = match (stop, direction) {
+            (EXIT, _) => usize::MAX,
+            (stop, Direction::Up | Direction::Down) => (stop as usize).abs_diff(idx) / self.width,
+            (stop, Direction::Left | Direction::Right) => (stop as usize).abs_diff(idx),
+        };
+
+        match distance {
+            Some(distance) if distance <= reach => Some(self.walk(idx, direction, distance - 1)),
+            _ => (stop != EXIT).then_some(stop as usize),
+        }
+    }
+
+    fn walk(&self, idx: usize, direction: Direction, steps: usize) -> usize {
+        match direction {
+            Direction::Up => idx - steps * self.width,
+            Direction::Down => idx + steps * self.width,
+            Direction::Left => idx - steps,
+            Direction::Right => idx + steps,
         }
+    }
 
-        if !room.contains(&guard.pos) {
-            break;
+    // simulates the guard from `idx` with an extra obstacle, jumping from turn to turn
+    fn loops(
+        &self,
+        (idx, direction, phase): (usize, Direction, usize),
+        policy: TurnPolicy,
+        obstacle: usize,
+        turns: &mut HashSet<usize>,
+    ) -> bool {
+        turns.clear();
+
+        let (mut idx, mut direction, mut phase) = (idx, direction, phase);
+        while let Some(stop) = self.next_stop(idx, direction, obstacle) {
+            if !turns.insert((stop * 4 + direction.index()) * 2 + phase) {
+                return true;
+            }
+            idx = stop;
+            direction = policy.turn(direction, phase);
+            phase = (phase + 1) % policy.phases();
         }
 
-        visited.insert(guard.pos);
+        false
     }
+}
 
-    let mut result = 0;
+/// The states of a simulated guard, one per step, starting with the initial state.
+/// Every step either moves the guard one cell or turns it.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Trajectory {
+    /// the guard walks out of the room after the last state
+    Exit(Vec<(Pos, Direction)>),
+    /// after the prefix, the guard repeats the cycle forever
+    Loop {
+        prefix: Vec<(Pos, Direction)>,
+        cycle: Vec<(Pos, Direction)>,
+    },
+}
 
-    for new_obstacle in visited.iter() {
-        let mut guard = initial_guard;
-        let mut local_visited = HashSet::new();
-        local_visited.insert(guard);
-        let must_delete = room.obstacles.insert(*new_obstacle);
+impl Trajectory {
+    pub fn states(&self) -> impl Iterator<Item = &(Pos, Direction)> {
+        let (prefix, cycle): (&[_], &[_]) = match self {
+            Trajectory::Exit(states) => (states, &[]),
+            Trajectory::Loop { prefix, cycle } => (prefix, cycle),
+        };
+        prefix.iter().chain(cycle)
+    }
 
-        loop {
-            let mut next_pos = guard.pos;
-            guard.direction.step(&mut next_pos);
+    /// The distinct positions the guard visits.
+    pub fn visited(&self) -> HashSet<Pos> {
+        self.states().map(|(pos, _)| *pos).collect()
+    }
+}
 
-            if !room.is_safe(&next_pos) {
-                guard.direction = guard.direction.turn_right();
-            } else {
-                guard.pos = next_pos;
-            }
+/// A guard walking through a room, by default turning right at every obstacle.
+#[derive(Debug)]
+pub struct GuardSimulation {
+    room: Room,
+    guard: Guard,
+    policy: TurnPolicy,
+}
+
+impl FromStr for GuardSimulation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(GuardSimulation {
+            room: s.parse()?,
+            guard: s.parse()?,
+            policy: TurnPolicy::default(),
+        })
+    }
+}
+
+impl GuardSimulation {
+    pub fn with_policy(mut self, policy: TurnPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
 
-            if !local_visited.insert(guard) {
-                result += 1;
+    pub fn run(&self) -> Trajectory {
+        self.run_with_obstacle(None)
+    }
+
+    /// Runs the simulation with an extra obstacle placed in the room.
+    pub fn run_with_obstacle(&self, obstacle: Option<Pos>) -> Trajectory {
+        self.room.trajectory(self.guard, self.policy, obstacle)
+    }
+
+    /// Every position where a single extra obstacle makes the guard walk in a loop,
+    /// in the order the guard originally reaches them.
+    pub fn looping_obstacles(&self) -> Vec<Pos> {
+        let room = &self.room;
+        let mut guard = self.guard;
+        let mut phase = 0;
+        let jumps = JumpTable::new(room);
+
+        // walk the original route once, every cell on it is a candidate for the new
+        // obstacle; the route is unchanged up to the first time the guard enters it
+        let mut seen = vec![false; room.width * room.height];
+        seen[room.index(&guard.pos)] = true;
+        let mut candidates = Vec::new();
+
+        // bit per direction and phase, in case the route is a loop by itself
+        let mut states = vec![0u8; room.width * room.height];
+
+        loop {
+            let before = (room.index(&guard.pos), guard.direction, phase);
+            let bit = 1 << (guard.direction.index() * 2 + phase);
+            if states[before.0] & bit != 0 {
                 break;
             }
+            states[before.0] |= bit;
 
-            if !room.contains(&guard.pos) {
+            if !room.step(&mut guard, &mut phase, self.policy, None) {
                 break;
             }
+
+            let idx = room.index(&guard.pos);
+            if !seen[idx] {
+                seen[idx] = true;
+                candidates.push((guard.pos, before));
+            }
+        }
+
+        candidates
+            .into_par_iter()
+            .map_init(HashSet::new, |turns, (obstacle, before)| {
+                let obstacle_idx = room.index(&obstacle);
+                let loops = jumps.loops(before, self.policy, obstacle_idx, turns);
+                loops.then_some(obstacle)
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+/// How a patrol of several guards ends.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum PatrolOutcome {
+    /// every guard has walked out of the room after this many steps
+    Exit { steps: usize },
+    /// after `step` steps two guards, identified by their index in the map, are on
+    /// the same cell or have just walked through each other, `pos` is where the
+    /// first of them is
+    Collision {
+        step: usize,
+        guards: (usize, usize),
+        pos: Pos,
+    },
+    /// after `start` steps every guard is either gone or walking in its cycle, the
+    /// combined state repeats every `length` steps from then on, `None` if that does
+    /// not fit in a usize
+    Loop { start: usize, length: Option<usize> },
+}
+
+/// All guards in a room stepping together, guards don't see each other as obstacles.
+#[derive(Debug)]
+pub struct Patrol {
+    room: Room,
+    guards: Vec<Guard>,
+    policy: TurnPolicy,
+}
+
+impl FromStr for Patrol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let guards = Guard::parse_all(s);
+        if guards.is_empty() {
+            return Err(Error {});
+        }
+
+        Ok(Patrol {
+            room: s.parse()?,
+            guards,
+            policy: TurnPolicy::default(),
+        })
+    }
+}
+
+impl Patrol {
+    pub fn with_policy(mut self, policy: TurnPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Starting positions and directions of the guards, row by row.
+    pub fn guards(&self) -> Vec<(Pos, Direction)> {
+        self.guards
+            .iter()
+            .map(|guard| (guard.pos, guard.direction))
+            .collect()
+    }
+
+    /// Each guard is simulated on its own, and every pair of guards is checked for
+    /// the first collision, so a patrol never steps through the combined cycle.
+    pub fn run(&self) -> PatrolOutcome {
+        let paths: Vec<Path> = self
+            .guards
+            .iter()
+            .map(|&guard| Path::new(self.room.trajectory(guard, self.policy, None)))
+            .collect();
+
+        let collision = (0..paths.len())
+            .flat_map(|i| (i + 1..paths.len()).map(move |j| (i, j)))
+            .filter_map(|(i, j)| {
+                let (step, pos) = paths[i].collision(&paths[j])?;
+                Some((step, (i, j), pos))
+            })
+            .min_by_key(|&(step, guards, _)| (step, guards));
+        if let Some((step, guards, pos)) = collision {
+            return PatrolOutcome::Collision { step, guards, pos };
+        }
+
+        if paths.iter().all(|path| path.cycle == 0) {
+            let steps = paths.iter().map(|path| path.states.len()).max();
+            return PatrolOutcome::Exit {
+                steps: steps.unwrap_or(0),
+            };
+        }
+        PatrolOutcome::Loop {
+            start: paths.iter().map(|path| path.prefix).max().unwrap_or(0),
+            length: paths
+                .iter()
+                .filter(|path| path.cycle > 0)
+                .try_fold(1, |length, path| lcm(length, path.cycle)),
+        }
+    }
+}
+
+// the positions of a single guard, one per step
+struct Path {
+    // the prefix followed by one round of the cycle
+    states: Vec<Pos>,
+    // steps before the cycle starts, or before the guard walks out
+    prefix: usize,
+    // 0 if the guard walks out
+    cycle: usize,
+}
+
+// the steps in the cycle where a guard is on each cell, and where it has just
+// moved from one cell to another
+type Events = (HashMap<Pos, Vec<usize>>, HashMap<(Pos, Pos), Vec<usize>>);
+
+impl Path {
+    fn new(trajectory: Trajectory) -> Self {
+        let prefix = match &trajectory {
+            Trajectory::Exit(states) => states.len(),
+            Trajectory::Loop { prefix, .. } => prefix.len(),
+        };
+        let states: Vec<_> = trajectory.states().map(|(pos, _)| *pos).collect();
+        Path {
+            cycle: states.len() - prefix,
+            states,
+            prefix,
+        }
+    }
+
+    fn at(&self, step: usize) -> Option<Pos> {
+        if step < self.states.len() {
+            Some(self.states[step])
+        } else if self.cycle > 0 {
+            Some(self.states[self.prefix + (step - self.prefix) % self.cycle])
+        } else {
+            None
+        }
+    }
+
+    fn events(&self) -> Events {
+        let (mut cells, mut moves): Events = Default::default();
+        for k in 0..self.cycle {
+            let pos = self.states[self.prefix + k];
+            let before = self.states[self.prefix + (k + self.cycle - 1) % self.cycle];
+            cells.entry(pos).or_default().push(k);
+            if before != pos {
+                moves.entry((before, pos)).or_default().push(k);
+            }
         }
+        (cells, moves)
+    }
 
-        if must_delete {
-            room.obstacles.remove(new_obstacle);
+    // the first step at which both guards are on the same cell or have just walked
+    // through each other, and where this guard is then
+    fn collision(&self, other: &Path) -> Option<(usize, Pos)> {
+        let hit = |step: usize| {
+            let (pos, other_pos) = (self.at(step)?, other.at(step)?);
+            let swapped = self.at(step - 1) == Some(other_pos) && other.at(step - 1) == Some(pos);
+            (pos == other_pos || swapped).then_some((step, pos))
+        };
+
+        // until both guards walk in their cycles, or one of them is gone
+        let settled = self.prefix.max(other.prefix);
+        if self.cycle == 0 || other.cycle == 0 {
+            let end = match (self.cycle, other.cycle) {
+                (0, 0) => self.states.len().min(other.states.len()),
+                (0, _) => self.states.len(),
+                _ => other.states.len(),
+            };
+            return (1..end).find_map(hit);
         }
+        if let Some(found) = (1..=settled).find_map(hit) {
+            return Some(found);
+        }
+
+        // from then on a collision is a matching pair of steps in both cycles
+        let first = |k: usize, l: usize| {
+            let step = first_common(
+                (self.prefix + k, self.cycle),
+                (other.prefix + l, other.cycle),
+                settled + 1,
+            )?;
+            usize::try_from(step).ok()
+        };
+        let ((cells, moves), (other_cells, other_moves)) = (self.events(), other.events());
+        let meetings = cells.iter().filter_map(|(pos, ks)| {
+            let ls = other_cells.get(pos)?;
+            Some((ks, ls, *pos))
+        });
+        let swaps = moves.iter().filter_map(|(&(from, to), ks)| {
+            let ls = other_moves.get(&(to, from))?;
+            Some((ks, ls, to))
+        });
+        meetings
+            .chain(swaps)
+            .flat_map(|(ks, ls, pos)| {
+                ks.iter()
+                    .flat_map(move |&k| ls.iter().map(move |&l| (k, l)))
+                    .filter_map(move |(k, l)| Some((first(k, l)?, pos)))
+            })
+            .min_by_key(|&(step, _)| step)
+    }
+}
+
+// the first step >= `from` that is `a` modulo `m` and `b` modulo `n`
+fn first_common((a, m): (usize, usize), (b, n): (usize, usize), from: usize) -> Option<u128> {
+    let (a, m, b, n, from) = (a as i128, m as i128, b as i128, n as i128, from as i128);
+    let (g, x, _) = extended_gcd(m, n);
+    if (b - a) % g != 0 {
+        return None;
     }
+    let lcm = m / g * n;
+    let k = ((b - a) / g % (n / g) * x).rem_euclid(n / g);
+    let step = (a + m * k).rem_euclid(lcm);
+    let step = if step < from {
+        step + (from - step + lcm - 1) / lcm * lcm
+    } else {
+        step
+    };
+    Some(step as u128)
+}
+
+// (g, x, y) with a * x + b * y = g
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - a / b * y)
+}
+
+fn lcm(a: usize, b: usize) -> Option<usize> {
+    let g = extended_gcd(a as i128, b as i128).0 as usize;
+    (a / g).checked_mul(b)
+}
 
-    result
+pub fn part1(input: &str) -> u32 {
+    let simulation = input.parse::<GuardSimulation>().unwrap();
+    simulation.run().visited().len() as u32
+}
+
+pub fn part2(input: &str) -> u32 {
+    let simulation = input.parse::<GuardSimulation>().unwrap();
+    simulation.looping_obstacles().len() as u32
 }
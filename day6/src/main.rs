@@ -1,4 +1,6 @@
 use day6::{part1, part2};
+#[cfg(test)]
+use day6::{Direction, GuardSimulation, Patrol, PatrolOutcome, Pos, Trajectory, TurnPolicy};
 use std::fs;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -21,13 +23,13 @@ fn test_part_1() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part1(&contents);
     assert_eq!(num, 41);
 
-    return Ok(());
+    Ok(())
 }
 
 #[test]
@@ -35,11 +37,164 @@ fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part2(&contents);
     assert_eq!(num, 6);
 
-    return Ok(());
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[test]
+fn test_part_2_big() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "input/dummy_big.txt";
+
+    // Read file contents
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+
+    assert_eq!(part1(&contents), 201);
+    assert_eq!(part2(&contents), 60);
+
+    Ok(())
+}
+
+#[test]
+fn test_trajectory() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "input/dummy.txt";
+
+    // Read file contents
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+
+    let simulation: GuardSimulation = contents.parse().map_err(|_| "invalid room")?;
+    let Trajectory::Exit(states) = simulation.run() else {
+        panic!("guard should leave the room");
+    };
+    assert_eq!(states[0], (Pos { x: 4, y: 6 }, Direction::Up));
+    assert_eq!(states[5], (Pos { x: 4, y: 1 }, Direction::Up));
+    assert_eq!(states[6], (Pos { x: 4, y: 1 }, Direction::Right));
+    assert_eq!(states.last(), Some(&(Pos { x: 7, y: 9 }, Direction::Down)));
+
+    let obstacles = simulation.looping_obstacles();
+    assert_eq!(obstacles.len(), 6);
+    assert!(obstacles.contains(&Pos { x: 3, y: 6 }));
+
+    for obstacle in obstacles {
+        let trajectory = simulation.run_with_obstacle(Some(obstacle));
+        let Trajectory::Loop { cycle, .. } = &trajectory else {
+            panic!("guard should walk in a loop");
+        };
+        assert_eq!(trajectory.states().next(), Some(&states[0]));
+        assert!(cycle.iter().all(|(pos, _)| *pos != obstacle));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_turn_policies() -> Result<(), Box<dyn std::error::Error>> {
+    let simulation: GuardSimulation = "#\n.\n^".parse().map_err(|_| "invalid room")?;
+    let simulation = simulation.with_policy(TurnPolicy::Reverse);
+    assert_eq!(
+        simulation.run(),
+        Trajectory::Exit(vec![
+            (Pos { x: 0, y: 2 }, Direction::Up),
+            (Pos { x: 0, y: 1 }, Direction::Up),
+            (Pos { x: 0, y: 1 }, Direction::Down),
+            (Pos { x: 0, y: 2 }, Direction::Down),
+        ])
+    );
+
+    let simulation: GuardSimulation = "#.\n^#".parse().map_err(|_| "invalid room")?;
+    let simulation = simulation.with_policy(TurnPolicy::Alternate);
+    assert_eq!(
+        simulation.run(),
+        Trajectory::Loop {
+            prefix: vec![],
+            cycle: vec![
+                (Pos { x: 0, y: 1 }, Direction::Up),
+                (Pos { x: 0, y: 1 }, Direction::Right),
+            ],
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_patrol() -> Result<(), Box<dyn std::error::Error>> {
+    let patrol: Patrol = ".....\n>...<\n.....".parse().map_err(|_| "invalid room")?;
+    assert_eq!(patrol.guards().len(), 2);
+    assert_eq!(
+        patrol.run(),
+        PatrolOutcome::Collision {
+            step: 2,
+            guards: (0, 1),
+            pos: Pos { x: 2, y: 1 }
+        }
+    );
+
+    let patrol: Patrol = "><..".parse().map_err(|_| "invalid room")?;
+    assert_eq!(
+        patrol.run(),
+        PatrolOutcome::Collision {
+            step: 1,
+            guards: (0, 1),
+            pos: Pos { x: 1, y: 0 }
+        }
+    );
+
+    let patrol: Patrol = ">...v\n.....".parse().map_err(|_| "invalid room")?;
+    assert_eq!(patrol.run(), PatrolOutcome::Exit { steps: 5 });
+
+    let patrol: Patrol = "#..#\n^..v\n#..#".parse().map_err(|_| "invalid room")?;
+    let patrol = patrol.with_policy(TurnPolicy::Reverse);
+    assert_eq!(
+        patrol.run(),
+        PatrolOutcome::Loop {
+            start: 0,
+            length: Some(2)
+        }
+    );
+
+    // cycles of 4 and 6 steps, while the last guard walks out after 5 steps
+    let patrol: Patrol = "#>.#.\n#>..#\n>....".parse().map_err(|_| "invalid room")?;
+    let patrol = patrol.with_policy(TurnPolicy::Reverse);
+    assert_eq!(
+        patrol.run(),
+        PatrolOutcome::Loop {
+            start: 5,
+            length: Some(12)
+        }
+    );
+
+    // guards bouncing in corridors of coprime-ish lengths, whose combined cycle is
+    // far too long to step through
+    let corridors = |lengths: &[usize]| -> Result<Patrol, &str> {
+        let room = lengths
+            .iter()
+            .map(|&n| format!("#>{}#{}", ".".repeat(n - 1), "#".repeat(1019 - n)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let patrol: Patrol = room.parse().map_err(|_| "invalid room")?;
+        Ok(patrol.with_policy(TurnPolicy::Reverse))
+    };
+    assert_eq!(
+        corridors(&[1000, 1001, 1003])?.run(),
+        PatrolOutcome::Loop {
+            start: 0,
+            length: Some(2008006000)
+        }
+    );
+    assert_eq!(
+        corridors(&[1000, 1001, 1003, 1007, 1009, 1013, 1019])?.run(),
+        PatrolOutcome::Loop {
+            start: 0,
+            length: None
+        }
+    );
+
+    Ok(())
+}
@@ -1,59 +1,260 @@
+use std::fmt;
 use std::str::FromStr;
-use std::collections::VecDeque;
 
 #[derive(Debug, PartialEq, Eq)]
-struct EquationError;
+pub struct EquationError;
 
 #[derive(Debug, PartialEq, Eq)]
-struct Equation {
-    ans: i64,
-    nums: Vec<i64>,
+pub struct Equation {
+    pub ans: i64,
+    pub nums: Vec<i64>,
 }
 
-fn concat(a: i64, b: i64) -> i64 {
-    let num_digits = i64::ilog10(b) + 1;
-    let result = a * i64::pow(10, num_digits as u32) + b;
-    return result;
+/// A binary operator which can be placed between the numbers of an equation,
+/// operators are always evaluated left-to-right.
+pub trait Operator {
+    /// The result of `a op b`, or None if it is undefined or overflows.
+    fn apply(&self, a: i128, b: i128) -> Option<i128>;
+
+    fn symbol(&self) -> &str;
+
+    /// The `a` for which `a op b == result`, or None if there is no such `a`. Only
+    /// called if `invertible` is true.
+    fn undo(&self, _result: i128, _b: i128) -> Option<i128> {
+        None
+    }
+
+    /// Whether `undo` finds `a` whenever it exists and it is unique for `b != 0`,
+    /// which allows solving equations backwards.
+    fn invertible(&self) -> bool {
+        false
+    }
+
+    /// Whether `a op b >= a` for all `a >= 0` and `b >= 1`, which allows the solver
+    /// to give up as soon as an intermediate result is larger than the answer.
+    fn never_decreases(&self) -> bool {
+        false
+    }
+}
+
+pub struct Add;
+pub struct Mul;
+pub struct Concat;
+
+impl Operator for Add {
+    fn apply(&self, a: i128, b: i128) -> Option<i128> {
+        a.checked_add(b)
+    }
+
+    fn symbol(&self) -> &str {
+        "+"
+    }
+
+    fn undo(&self, result: i128, b: i128) -> Option<i128> {
+        result.checked_sub(b)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn never_decreases(&self) -> bool {
+        true
+    }
+}
+
+impl Operator for Mul {
+    fn apply(&self, a: i128, b: i128) -> Option<i128> {
+        a.checked_mul(b)
+    }
+
+    fn symbol(&self) -> &str {
+        "*"
+    }
+
+    fn undo(&self, result: i128, b: i128) -> Option<i128> {
+        if b == 0 || result.checked_rem(b)? != 0 {
+            return None;
+        }
+        result.checked_div(b)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn never_decreases(&self) -> bool {
+        true
+    }
+}
+
+impl Operator for Concat {
+    fn apply(&self, a: i128, b: i128) -> Option<i128> {
+        if b < 0 {
+            return None;
+        }
+        a.checked_mul(digits_pow(b)?)?.checked_add(b)
+    }
+
+    fn symbol(&self) -> &str {
+        "||"
+    }
+
+    fn undo(&self, result: i128, b: i128) -> Option<i128> {
+        if b < 0 || result < 0 {
+            return None;
+        }
+        // the digits of b have to be the suffix of result
+        let pow = digits_pow(b)?;
+        (result % pow == b).then_some(result / pow)
+    }
+
+    fn invertible(&self) -> bool {
+        true
+    }
+
+    fn never_decreases(&self) -> bool {
+        true
+    }
+}
+
+// 10 to the power of the number of digits in b
+fn digits_pow(b: i128) -> Option<i128> {
+    let num_digits = b.checked_ilog10().unwrap_or(0) + 1;
+    10i128.checked_pow(num_digits)
+}
+
+/// The operators that make an equation true.
+pub struct Solution<'a> {
+    pub equation: &'a Equation,
+    pub operators: Vec<&'a dyn Operator>,
+}
+
+impl fmt::Display for Solution<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.equation.ans, self.equation.nums[0])?;
+        for (operator, num) in self.operators.iter().zip(&self.equation.nums[1..]) {
+            write!(f, " {} {}", operator.symbol(), num)?;
+        }
+        Ok(())
+    }
 }
 
 impl Equation {
-    fn is_valid(&self, use_concat: bool) -> bool {
+    /// Finds operators, tried in the given order, that make the equation true.
+    pub fn solve<'a>(&'a self, operators: &[&'a dyn Operator]) -> Option<Solution<'a>> {
+        let prune = operators.iter().all(|operator| operator.never_decreases())
+            && self.nums[0] >= 0
+            && self.nums[1..].iter().all(|&num| num >= 1);
 
-        #[derive(Debug)]
-        struct Item {
-            idx: usize,
-            intermediate: i64,
+        let mut chosen = Vec::with_capacity(self.nums.len().saturating_sub(1));
+        if self.search(operators, prune, self.nums[0] as i128, &mut chosen) {
+            Some(Solution {
+                equation: self,
+                operators: chosen,
+            })
+        } else {
+            None
         }
+    }
 
-        let mut queue = VecDeque::new();
-        queue.push_front(Item{idx: 0, intermediate: self.nums[0] * self.nums[1]});
-        if use_concat {
-            queue.push_front(Item{idx: 0, intermediate: concat(self.nums[0], self.nums[1])});
+    // depth first search over the operators, `chosen` holds the operators in front
+    // of `intermediate`
+    fn search<'a>(
+        &self,
+        operators: &[&'a dyn Operator],
+        prune: bool,
+        intermediate: i128,
+        chosen: &mut Vec<&'a dyn Operator>,
+    ) -> bool {
+        if prune && intermediate > self.ans as i128 {
+            return false;
         }
-        queue.push_front(Item{idx: 0, intermediate: self.nums[0] + self.nums[1]});
 
-        while let Some(item) = queue.pop_front() {
-            if item.intermediate > self.ans {
+        let Some(&num) = self.nums.get(chosen.len() + 1) else {
+            return intermediate == self.ans as i128;
+        };
+
+        for &operator in operators {
+            let Some(next) = operator.apply(intermediate, num as i128) else {
                 continue;
+            };
+
+            chosen.push(operator);
+            if self.search(operators, prune, next, chosen) {
+                return true;
             }
+            chosen.pop();
+        }
 
-            if item.idx == self.nums.len() - 2 {
-                if item.intermediate == self.ans {
-                    return true;
-                }
+        false
+    }
+
+    /// Like `solve`, but works from the answer back to the first number by undoing
+    /// the operators, which cuts off most branches early. Falls back to `solve` if
+    /// an operator can't be undone, or for a `0` that makes undoing ambiguous.
+    pub fn solve_backward<'a>(&'a self, operators: &[&'a dyn Operator]) -> Option<Solution<'a>> {
+        if !operators.iter().all(|operator| operator.invertible()) || self.nums[1..].contains(&0) {
+            return self.solve(operators);
+        }
+
+        let prune = operators.iter().all(|operator| operator.never_decreases())
+            && self.nums[0] >= 0
+            && self.nums[1..].iter().all(|&num| num >= 1);
+
+        let mut chosen = Vec::with_capacity(self.nums.len() - 1);
+        if !self.search_backward(operators, prune, self.ans as i128, &mut chosen) {
+            return None;
+        }
+
+        chosen.reverse();
+        Some(Solution {
+            equation: self,
+            operators: chosen,
+        })
+    }
+
+    // `chosen` holds the operators behind `target`, last one first
+    fn search_backward<'a>(
+        &self,
+        operators: &[&'a dyn Operator],
+        prune: bool,
+        target: i128,
+        chosen: &mut Vec<&'a dyn Operator>,
+    ) -> bool {
+        let idx = self.nums.len() - 1 - chosen.len();
+        let first = self.nums[0] as i128;
+        if idx == 0 {
+            return target == first;
+        }
+
+        // every intermediate result is at least the first number
+        if prune && target < first {
+            return false;
+        }
+
+        for &operator in operators {
+            let Some(previous) = operator.undo(target, self.nums[idx] as i128) else {
                 continue;
-            }
+            };
 
-            let num = self.nums[item.idx + 2];
-            if use_concat {
-                queue.push_front(Item{idx: item.idx+1, intermediate: concat(item.intermediate, num)});
+            chosen.push(operator);
+            if self.search_backward(operators, prune, previous, chosen) {
+                return true;
             }
-            queue.push_front(Item{idx: item.idx+1, intermediate: item.intermediate * num});
-            queue.push_front(Item{idx: item.idx+1, intermediate: item.intermediate + num});
+            chosen.pop();
         }
 
         false
     }
+
+    fn is_valid(&self, use_concat: bool) -> bool {
+        if use_concat {
+            self.solve_backward(&[&Add, &Mul, &Concat]).is_some()
+        } else {
+            self.solve_backward(&[&Add, &Mul]).is_some()
+        }
+    }
 }
 
 impl FromStr for Equation {
@@ -75,7 +276,10 @@ impl FromStr for Equation {
 pub fn part1(input: &str) -> i64 {
     input
         .lines()
-        .map(|line| line.parse::<Equation>().expect(&format!("invalid line \"{line}\"")))
+        .map(|line| {
+            line.parse::<Equation>()
+                .unwrap_or_else(|_| panic!("invalid line \"{line}\""))
+        })
         .filter(|equation| equation.is_valid(false))
         .map(|equation| equation.ans)
         .sum::<i64>()
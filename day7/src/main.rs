@@ -1,4 +1,6 @@
 use day7::{part1, part2};
+#[cfg(test)]
+use day7::{Add, Concat, Equation, Mul, Operator};
 use std::fs;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -22,13 +24,13 @@ fn test_part_1() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part1(&contents);
     assert_eq!(num, 3749);
 
-    return Ok(());
+    Ok(())
 }
 
 #[test]
@@ -36,11 +38,67 @@ fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part2(&contents);
     assert_eq!(num, 11387);
 
-    return Ok(());
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[test]
+fn test_solve() -> Result<(), Box<dyn std::error::Error>> {
+    let equation: Equation = "3267: 81 40 27".parse().map_err(|_| "invalid equation")?;
+    let solution = equation.solve(&[&Add, &Mul]).ok_or("no solution")?;
+    assert_eq!(solution.to_string(), "3267 = 81 + 40 * 27");
+
+    let equation: Equation = "7290: 6 8 6 15".parse().map_err(|_| "invalid equation")?;
+    assert!(equation.solve(&[&Add, &Mul]).is_none());
+    let solution = equation
+        .solve(&[&Add, &Mul, &Concat])
+        .ok_or("no solution")?;
+    assert_eq!(solution.to_string(), "7290 = 6 * 8 || 6 * 15");
+
+    struct Sub;
+    impl Operator for Sub {
+        fn apply(&self, a: i128, b: i128) -> Option<i128> {
+            a.checked_sub(b)
+        }
+
+        fn symbol(&self) -> &str {
+            "-"
+        }
+    }
+
+    let equation: Equation = "5: 10 0 2 3".parse().map_err(|_| "invalid equation")?;
+    let solution = equation.solve(&[&Add, &Mul, &Sub]).ok_or("no solution")?;
+    assert_eq!(solution.to_string(), "5 = 10 + 0 - 2 - 3");
+
+    Ok(())
+}
+
+#[test]
+fn test_solve_backward() -> Result<(), Box<dyn std::error::Error>> {
+    let equation: Equation = "3267: 81 40 27".parse().map_err(|_| "invalid equation")?;
+    let solution = equation
+        .solve_backward(&[&Add, &Mul])
+        .ok_or("no solution")?;
+    assert_eq!(solution.to_string(), "3267 = 81 * 40 + 27");
+
+    // too long to try every combination of operators
+    let equation: Equation = "721442690571910852: 3 4 4 13 7 11 10 8 3 20 7 15 14 18 13 19 16 \
+        18 10 3 2 13 16 12 14 15 18 7 19 7 9 9 2 7 12 7 6 18 18 13"
+        .parse()
+        .map_err(|_| "invalid equation")?;
+    let operators: [&dyn Operator; 3] = [&Add, &Mul, &Concat];
+    let solution = equation.solve_backward(&operators).ok_or("no solution")?;
+
+    let mut result = equation.nums[0] as i128;
+    for (op, &num) in solution.operators.iter().zip(&equation.nums[1..]) {
+        result = op.apply(result, num as i128).ok_or("overflow")?;
+    }
+    assert_eq!(result, equation.ans as i128);
+
+    Ok(())
+}
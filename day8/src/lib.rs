@@ -4,26 +4,29 @@ use std::iter::Iterator;
 use std::str::FromStr;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-struct Pos {
-    x: i64,
-    y: i64,
+pub struct Pos {
+    pub x: i64,
+    pub y: i64,
 }
 
 #[derive(Debug, PartialEq, Eq)]
-struct Map {
-    // pos_to_antenna: HashMap<Pos, char>,
+pub struct Map {
+    pos_to_antenna: HashMap<Pos, char>,
     antenna_to_pos: HashMap<char, Vec<Pos>>,
     size: (usize, usize),
 }
 
 #[derive(Debug, PartialEq, Eq)]
-struct ParseError;
+pub struct ParseError;
+
+const EMPTY: char = '.';
+const ANTINODE: char = '#';
 
 impl FromStr for Map {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // let mut pos_to_antenna = HashMap::new();
+        let mut pos_to_antenna = HashMap::new();
         let mut antenna_to_pos = HashMap::new();
         let first = s.lines().next().ok_or(ParseError {})?;
         let width = first.len();
@@ -36,86 +39,234 @@ impl FromStr for Map {
                     y: y as i64,
                 };
 
-                // pos_to_antenna.insert(pos, char);
-
-                if char == '.' {
+                if char == EMPTY {
                     continue;
                 }
 
+                pos_to_antenna.insert(pos, char);
+
                 let entry = antenna_to_pos.entry(char).or_insert(Vec::new());
-                entry.push(pos.clone());
+                entry.push(pos);
             }
         }
 
         Ok(Map {
-            // pos_to_antenna,
+            pos_to_antenna,
             antenna_to_pos,
             size: (width, height),
         })
     }
 }
 
+/// Where antinodes appear on the line through two antennas of the same frequency.
+/// Positions on that line are `first + m * (second - first)`, so the antennas
+/// themselves are at `m = 0` and `m = 1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResonanceRule {
+    /// where one antenna is exactly `k` times as far away as the other, both beyond
+    /// and between the antennas, the puzzle uses 2
+    Ratio(i64),
+    /// where one antenna is exactly `k` times as far away as the other for any of the `k`
+    Ratios(Vec<i64>),
+    /// at every multiple of the distance between the antennas, including the antennas
+    AllMultiples,
+    /// at the antennas and at most this many multiples beyond each of them
+    MaxHarmonics(usize),
+}
+
+impl ResonanceRule {
+    // the multiples allowed by the rules that only use whole multiples
+    fn allows(&self, m: i64) -> bool {
+        match self {
+            ResonanceRule::Ratio(_) | ResonanceRule::Ratios(_) => false,
+            ResonanceRule::AllMultiples => true,
+            ResonanceRule::MaxHarmonics(n) => -(*n as i64) <= m && m <= 1 + *n as i64,
+        }
+    }
+
+    // the multiples allowed by the ratio rules as sorted `(numerator, denominator)`
+    // fractions, which may fall between cells
+    fn fractions(&self) -> Option<Vec<(i64, i64)>> {
+        let ks = match self {
+            ResonanceRule::Ratio(k) => vec![*k],
+            ResonanceRule::Ratios(ks) => ks.clone(),
+            _ => return None,
+        };
+
+        // |m| = k * |m - 1| or |m - 1| = k * |m|
+        let mut fractions: Vec<(i64, i64)> = ks
+            .into_iter()
+            .filter(|&k| k > 0)
+            .flat_map(|k| {
+                let mut fractions = vec![(1, k + 1), (k, k + 1)];
+                if k > 1 {
+                    fractions.extend([(-1, k - 1), (k, k - 1)]);
+                }
+                fractions
+            })
+            .collect();
+        fractions.sort_by(|a, b| (a.0 * b.1).cmp(&(b.0 * a.1)));
+        fractions.dedup_by(|a, b| a.0 * b.1 == b.0 * a.1);
+        Some(fractions)
+    }
+}
+
+/// An antinode together with the pair of antennas that produced it.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Antinode {
+    pub pos: Pos,
+    pub frequency: char,
+    pub antennas: (Pos, Pos),
+}
+
 impl Map {
     fn contains(&self, pos: &Pos) -> bool {
         pos.x >= 0 && pos.y >= 0 && pos.x < self.size.0 as i64 && pos.y < self.size.1 as i64
     }
 
-    fn compute_antinodes(&self) -> HashSet<Pos> {
-        let combinations = self
-            .antenna_to_pos
-            .iter()
-            .flat_map(|(_, pos)| pos.into_iter().tuple_combinations())
-            .flat_map(|(first, second)| {
-                let x1 = 2 * second.x - first.x;
-                let y1 = 2 * second.y - first.y;
-                let x2 = 2 * first.x - second.x;
-                let y2 = 2 * first.y - second.y;
-
-                let first = Pos { x: x1, y: y1 };
-                let second = Pos { x: x2, y: y2 };
+    /// The frequencies of all antennas, sorted.
+    pub fn frequencies(&self) -> Vec<char> {
+        self.antenna_to_pos.keys().copied().sorted().collect()
+    }
 
-                [first, second]
+    /// Every antinode within the map for every pair of antennas, optionally only for
+    /// the given frequencies. The same position shows up once for every pair that
+    /// produces an antinode there.
+    pub fn antinodes(&self, rule: &ResonanceRule, frequencies: Option<&[char]>) -> Vec<Antinode> {
+        self.frequencies()
+            .into_iter()
+            .filter(|frequency| frequencies.is_none_or(|f| f.contains(frequency)))
+            .flat_map(|frequency| {
+                self.antenna_to_pos[&frequency]
+                    .iter()
+                    .tuple_combinations()
+                    .flat_map(move |(&first, &second)| {
+                        self.on_line(first, second, rule)
+                            .into_iter()
+                            .map(move |pos| Antinode {
+                                pos,
+                                frequency,
+                                antennas: (first, second),
+                            })
+                    })
             })
-            .filter(|antinode| self.contains(antinode));
+            .collect()
+    }
+
+    // the positions on the line through both antennas allowed by the rule
+    fn on_line(&self, first: Pos, second: Pos, rule: &ResonanceRule) -> Vec<Pos> {
+        let dx = second.x - first.x;
+        let dy = second.y - first.y;
+        let at = |m: i64| Pos {
+            x: first.x + m * dx,
+            y: first.y + m * dy,
+        };
+
+        if let Some(fractions) = rule.fractions() {
+            return fractions
+                .into_iter()
+                .filter(|&(n, d)| (n * dx) % d == 0 && (n * dy) % d == 0)
+                .map(|(n, d)| Pos {
+                    x: first.x + n * dx / d,
+                    y: first.y + n * dy / d,
+                })
+                .filter(|pos| self.contains(pos))
+                .collect();
+        }
+
+        // walk outwards from both antennas until leaving the map
+        let lefts: Vec<_> = (0..)
+            .map(|i: i64| -i)
+            .take_while(|&m| self.contains(&at(m)))
+            .collect();
+        let rights = (1..).take_while(|&m| self.contains(&at(m)));
 
-        return combinations.collect();
+        lefts
+            .into_iter()
+            .rev()
+            .chain(rights)
+            .filter(|&m| rule.allows(m))
+            .map(at)
+            .collect()
     }
+}
+
+/// How a rendered map is written.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Style {
+    #[default]
+    Plain,
+    /// every frequency and its antinodes in their own colour, using ANSI escape codes
+    Ansi,
+}
 
-    fn compute_harmonics(&self) -> HashSet<Pos> {
-        let combinations = self
-            .antenna_to_pos
+// foreground colours for the frequencies, reused when there are more frequencies
+const COLOURS: [u8; 6] = [31, 32, 33, 34, 35, 36];
+
+impl Map {
+    /// The map with antinodes drawn as `#` on the empty cells, optionally showing just
+    /// the antennas and antinodes of a single frequency.
+    pub fn render(&self, antinodes: &[Antinode], frequency: Option<char>, style: Style) -> String {
+        let shown = |f: char| frequency.is_none_or(|frequency| frequency == f);
+        let antinode_at: HashMap<Pos, char> = antinodes
             .iter()
-            .flat_map(|(_, pos)| pos.into_iter().tuple_combinations())
-            .flat_map(|(first, second)| {
-                let dx = second.x - first.x;
-                let dy = second.y - first.y;
-
-                let lefts = (0..)
-                    .map(move |i| Pos {
-                        x: first.x - i * dx,
-                        y: first.y - i * dy,
-                    })
-                    .take_while(|antinode| self.contains(antinode));
-                let rights = (1..)
-                    .map(move |i| Pos {
-                        x: first.x + i * dx,
-                        y: first.y + i * dy,
-                    })
-                    .take_while(|antinode| self.contains(antinode));
+            .filter(|antinode| shown(antinode.frequency))
+            .map(|antinode| (antinode.pos, antinode.frequency))
+            .collect();
+        let frequencies = self.frequencies();
+        let colour = |f: char| {
+            let idx = frequencies.iter().position(|&other| other == f);
+            COLOURS[idx.unwrap_or(0) % COLOURS.len()]
+        };
 
-                lefts.chain(rights)
-            });
+        let mut result = String::new();
+        for y in 0..self.size.1 as i64 {
+            for x in 0..self.size.0 as i64 {
+                let pos = Pos { x, y };
+                let cell = match (self.pos_to_antenna.get(&pos), antinode_at.get(&pos)) {
+                    (Some(&antenna), _) if shown(antenna) => Some((antenna, antenna, true)),
+                    (_, Some(&frequency)) => Some((ANTINODE, frequency, false)),
+                    _ => None,
+                };
+
+                match (cell, style) {
+                    (None, _) => result.push(EMPTY),
+                    (Some((c, _, _)), Style::Plain) => result.push(c),
+                    (Some((c, frequency, bold)), Style::Ansi) => {
+                        let weight = if bold { "1;" } else { "" };
+                        let colour = colour(frequency);
+                        result.push_str(&format!("\x1b[{}{}m{}\x1b[0m", weight, colour, c));
+                    }
+                }
+            }
+            result.push('\n');
+        }
+        result
+    }
 
-        return combinations.collect();
+    /// One rendering per frequency, with only its antennas and antinodes.
+    pub fn render_layers(&self, rule: &ResonanceRule, style: Style) -> Vec<(char, String)> {
+        let antinodes = self.antinodes(rule, None);
+        self.frequencies()
+            .into_iter()
+            .map(|frequency| (frequency, self.render(&antinodes, Some(frequency), style)))
+            .collect()
     }
 }
 
+/// The distinct positions of a list of antinodes.
+pub fn unique_positions(antinodes: &[Antinode]) -> HashSet<Pos> {
+    antinodes.iter().map(|antinode| antinode.pos).collect()
+}
+
 pub fn part1(input: &str) -> u64 {
     let map = input.parse::<Map>().expect("could not parse map");
-    map.compute_antinodes().len() as u64
+    let antinodes = map.antinodes(&ResonanceRule::Ratio(2), None);
+    unique_positions(&antinodes).len() as u64
 }
 
 pub fn part2(input: &str) -> u64 {
     let map = input.parse::<Map>().expect("could not parse map");
-    map.compute_harmonics().len() as u64
+    let antinodes = map.antinodes(&ResonanceRule::AllMultiples, None);
+    unique_positions(&antinodes).len() as u64
 }
@@ -1,19 +1,63 @@
-use day8::{part1, part2};
+use day8::{part1, part2, Map, ResonanceRule, Style};
+#[cfg(test)]
+use day8::{unique_positions, Pos};
 use std::fs;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let file_path = std::env::args().nth(1).ok_or("Usage: day1 <file_to_txt>")?;
+    let file_path = std::env::args()
+        .nth(1)
+        .ok_or("Usage: day8 <file_to_txt> [--render | --layers] [--frequency=<c>] [--color]")?;
     let contents = fs::read_to_string(&file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     println!("Q1: How many unique locations within the bounds of the map contain an antinode?");
     let num = part1(&contents);
-    println!("A1: {}", nxum);
+    println!("A1: {}", num);
 
     println!("Q2: How many unique locations within the bounds of the map contain an antinode?");
     let num = part2(&contents);
     println!("A2: {}", num);
 
+    let options: Vec<_> = std::env::args().skip(2).collect();
+    if options.iter().any(|o| o == "--render" || o == "--layers") {
+        render(&contents, &options)?;
+    }
+
+    Ok(())
+}
+
+fn render(contents: &str, options: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let map = contents.parse::<Map>().map_err(|_| "could not parse map")?;
+    let style = if options.iter().any(|o| o == "--color") {
+        Style::Ansi
+    } else {
+        Style::Plain
+    };
+    let frequency = options
+        .iter()
+        .find_map(|o| o.strip_prefix("--frequency="))
+        .and_then(|f| f.chars().next());
+
+    for (name, rule) in [
+        ("part 1", ResonanceRule::Ratio(2)),
+        ("part 2", ResonanceRule::AllMultiples),
+    ] {
+        if options.iter().any(|o| o == "--layers") {
+            let layers = map.render_layers(&rule, style);
+            for (frequency, layer) in layers
+                .into_iter()
+                .filter(|(f, _)| frequency.is_none_or(|frequency| frequency == *f))
+            {
+                println!("\n{} antinodes of frequency {}:", name, frequency);
+                print!("{}", layer);
+            }
+        } else {
+            println!("\n{} antinodes:", name);
+            let antinodes = map.antinodes(&rule, None);
+            print!("{}", map.render(&antinodes, frequency, style));
+        }
+    }
+
     Ok(())
 }
 
@@ -22,13 +66,13 @@ fn test_part_1() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part1(&contents);
     assert_eq!(num, 14);
 
-    return Ok(());
+    Ok(())
 }
 
 #[test]
@@ -36,11 +80,107 @@ fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part2(&contents);
     assert_eq!(num, 34);
 
-    return Ok(());
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[test]
+fn test_resonance_rules() -> Result<(), Box<dyn std::error::Error>> {
+    let map: Map =
+        "..........\n...a......\n..........\n....a.....\n..........\n..........\n.........."
+            .parse()
+            .map_err(|_| "invalid map")?;
+    let a = (Pos { x: 3, y: 1 }, Pos { x: 4, y: 3 });
+
+    let antinodes = map.antinodes(&ResonanceRule::Ratio(2), Some(&['a']));
+    assert_eq!(antinodes.len(), 1);
+    assert_eq!(antinodes[0].pos, Pos { x: 5, y: 5 });
+    assert_eq!(antinodes[0].antennas, a);
+    assert!(map
+        .antinodes(&ResonanceRule::Ratio(2), Some(&['b']))
+        .is_empty());
+
+    let antinodes = map.antinodes(&ResonanceRule::Ratios(vec![2, 3]), None);
+    let positions: Vec<_> = antinodes.iter().map(|antinode| antinode.pos).collect();
+    assert_eq!(positions, [Pos { x: 5, y: 5 }]);
+
+    let antinodes = map.antinodes(&ResonanceRule::MaxHarmonics(0), None);
+    assert_eq!(unique_positions(&antinodes).len(), 2);
+
+    let antinodes = map.antinodes(&ResonanceRule::AllMultiples, None);
+    let positions: Vec<_> = antinodes.iter().map(|antinode| antinode.pos).collect();
+    assert_eq!(
+        positions,
+        [Pos { x: 3, y: 1 }, Pos { x: 4, y: 3 }, Pos { x: 5, y: 5 }]
+    );
+
+    // 3 and 1 cells away, or equally far away at the midpoint
+    let map: Map = "a.a.......".parse().map_err(|_| "invalid map")?;
+    let positions = |map: &Map, rule| -> Vec<i64> {
+        map.antinodes(&rule, None)
+            .iter()
+            .map(|antinode| antinode.pos.x)
+            .collect()
+    };
+    assert_eq!(positions(&map, ResonanceRule::Ratio(3)), [3]);
+    assert_eq!(positions(&map, ResonanceRule::Ratio(1)), [1]);
+    assert_eq!(positions(&map, ResonanceRule::Ratios(vec![2, 3])), [3, 4]);
+
+    let map: Map = "a..a......".parse().map_err(|_| "invalid map")?;
+    assert_eq!(positions(&map, ResonanceRule::Ratio(2)), [1, 2, 6]);
+
+    Ok(())
+}
+
+#[test]
+fn test_render() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "input/dummy.txt";
+
+    // Read file contents
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+
+    let map: Map = contents.parse().map_err(|_| "invalid map")?;
+    let antinodes = map.antinodes(&ResonanceRule::Ratio(2), None);
+    assert_eq!(
+        map.render(&antinodes, None, Style::Plain),
+        "......#....#\n\
+         ...#....0...\n\
+         ....#0....#.\n\
+         ..#....0....\n\
+         ....0....#..\n\
+         .#....A.....\n\
+         ...#........\n\
+         #......#....\n\
+         ........A...\n\
+         .........A..\n\
+         ..........#.\n\
+         ..........#.\n"
+    );
+
+    let layers = map.render_layers(&ResonanceRule::Ratio(2), Style::Plain);
+    assert_eq!(layers.len(), 2);
+    assert_eq!(layers[1].0, 'A');
+    assert_eq!(
+        layers[1].1,
+        "............\n\
+         ...#........\n\
+         ....#.......\n\
+         ............\n\
+         ............\n\
+         ......A.....\n\
+         ............\n\
+         .......#....\n\
+         ........A...\n\
+         .........A..\n\
+         ..........#.\n\
+         ..........#.\n"
+    );
+
+    Ok(())
+}
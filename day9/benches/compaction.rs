@@ -0,0 +1,83 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day9::part2;
+
+// a dense map of `len` digits, ending on a free span so that the old
+// implementation can index it
+fn generate(len: usize, seed: u64) -> String {
+    let mut state = seed | 1;
+    (0..len)
+        .map(|i| {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let digit = (state % 10) as u32;
+            let digit = if i % 2 == 0 { digit.max(1) } else { digit };
+            char::from_digit(digit, 10).unwrap()
+        })
+        .collect()
+}
+
+// part 2 as it was before the free-span heaps, scanning every free span
+// for each file
+fn part2_scan(input: &str) -> u64 {
+    let nums: Vec<_> = input
+        .chars()
+        .map(|c| c.to_digit(10).unwrap() as usize)
+        .collect();
+
+    let rev_blocks = nums.iter().step_by(2).enumerate().rev();
+    let mut frees: Vec<_> = nums.clone().into_iter().skip(1).step_by(2).collect();
+    let mut indexed = vec![vec![]; frees.len()];
+
+    for (rev_block, rev_size) in rev_blocks {
+        let my_idx = rev_block;
+        let free_idx = frees.iter().take(my_idx).position(|free| free >= rev_size);
+        if let Some(free_idx) = free_idx {
+            frees[free_idx] -= *rev_size;
+            indexed[free_idx].push((rev_size, rev_block));
+            frees[my_idx - 1] += rev_size;
+            continue;
+        }
+        indexed[my_idx].insert(0, (rev_size, rev_block));
+    }
+
+    let mut result: u64 = 0;
+    let mut factor = 0;
+    for (num_free, items) in frees.iter().zip(indexed) {
+        for (size, item) in items {
+            let size = *size;
+            result += ((size * item * (2 * factor + size - 1)) / 2) as u64;
+            factor += size;
+        }
+        factor += num_free;
+    }
+    result
+}
+
+fn bench_part2(c: &mut Criterion) {
+    let mut group = c.benchmark_group("part2");
+    group.sample_size(10);
+
+    for len in [10_000, 100_000, 1_000_000] {
+        let input = generate(len, 0x9e3779b97f4a7c15);
+        group.bench_with_input(BenchmarkId::new("heaps", len), &input, |b, input| {
+            b.iter(|| part2(input))
+        });
+
+        // the scan takes about a minute per run on a million digits, so it
+        // is only measured on the smaller maps
+        if len > 100_000 {
+            continue;
+        }
+        assert_eq!(part2(&input), part2_scan(&input));
+        group.bench_with_input(BenchmarkId::new("scan", len), &input, |b, input| {
+            b.iter(|| part2_scan(input))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_part2);
+criterion_main!(benches);
@@ -1,89 +1,311 @@
-pub fn part1(input: &str) -> u64 {
-    let nums: Vec<_> = input
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as u64)
-        .collect();
-
-    let blocks = nums
-        .iter()
-        .step_by(2)
-        .enumerate()
-        .map(|(i, n)| (0..*n).map(move |_| i));
-
-    let mut rev_blocks = blocks.clone().flatten().rev();
-    let total_nums: usize = rev_blocks.clone().count();
-
-    let frees = nums.iter().skip(1).step_by(2);
-    let filesystem = blocks.zip(frees);
-
-    let compressed = filesystem
-        .flat_map(|(block, free)| {
-            let size = *free as usize;
-            let taken: Vec<_> = rev_blocks.by_ref().take(size).collect();
-            block.chain(taken)
-        })
-        .take(total_nums);
-
-    compressed
-        .enumerate()
-        .map(|(i, n)| (i * n) as u64)
-        .sum::<u64>()
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A contiguous run of blocks belonging to one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extent {
+    pub id: usize,
+    pub start: usize,
+    pub len: usize,
 }
 
-pub fn part2(input: &str) -> u64 {
-    let nums: Vec<_> = input
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as usize)
-        .collect();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError;
+
+/// How [`DiskMap::compact`] moves data towards the start of the disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compaction {
+    /// Move single blocks from the end into the leftmost free block,
+    /// splitting files as needed.
+    #[default]
+    Blocks,
+    /// Move whole files, in decreasing id order, into the leftmost free span
+    /// that fits them. Each move costs O(log n).
+    FirstFit,
+    /// Like `FirstFit`, but pick the smallest free span that fits.
+    BestFit,
+    /// Gather every file into one piece and pack them with no gaps,
+    /// keeping the order in which they first appear.
+    Defragment,
+}
+
+/// Files laid out on a disk, with everything in between being free space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskMap {
+    // sorted by start, never overlapping
+    extents: Vec<Extent>,
+    size: usize,
+}
+
+impl FromStr for DiskMap {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut extents = vec![];
+        let mut pos = 0;
+        for (i, c) in s.trim().chars().enumerate() {
+            let len = c.to_digit(10).ok_or(ParseError)? as usize;
+            if i % 2 == 0 && len > 0 {
+                extents.push(Extent {
+                    id: i / 2,
+                    start: pos,
+                    len,
+                });
+            }
+            pos += len;
+        }
+
+        Ok(DiskMap { extents, size: pos })
+    }
+}
+
+impl DiskMap {
+    /// Total number of blocks on the disk, used or free.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The file extents, ordered by position.
+    pub fn extents(&self) -> &[Extent] {
+        &self.extents
+    }
+
+    /// The free spans as `(start, len)`, ordered by position.
+    pub fn free_spans(&self) -> Vec<(usize, usize)> {
+        let mut spans = vec![];
+        let mut pos = 0;
+        for extent in &self.extents {
+            if extent.start > pos {
+                spans.push((pos, extent.start - pos));
+            }
+            pos = extent.start + extent.len;
+        }
+        if self.size > pos {
+            spans.push((pos, self.size - pos));
+        }
+        spans
+    }
+
+    /// Expands the map into one entry per block, `None` being free.
+    pub fn blocks(&self) -> Vec<Option<usize>> {
+        let mut blocks = vec![None; self.size];
+        for extent in &self.extents {
+            blocks[extent.start..extent.start + extent.len].fill(Some(extent.id));
+        }
+        blocks
+    }
+
+    pub fn checksum(&self) -> u64 {
+        self.extents
+            .iter()
+            .map(|e| {
+                // sum of positions start..start + len
+                let positions = e.len * (2 * e.start + e.len - 1) / 2;
+                (e.id * positions) as u64
+            })
+            .sum()
+    }
+
+    pub fn compact(&self, strategy: Compaction) -> DiskMap {
+        match strategy {
+            Compaction::Blocks => self.compact_blocks(),
+            Compaction::FirstFit => self.compact_first_fit(),
+            Compaction::BestFit => self.compact_best_fit(),
+            Compaction::Defragment => self.defragment(),
+        }
+    }
+
+    fn compact_blocks(&self) -> DiskMap {
+        let mut blocks = self.blocks();
+        if blocks.is_empty() {
+            return self.clone();
+        }
+
+        let (mut i, mut j) = (0, blocks.len() - 1);
+        while i < j {
+            if blocks[i].is_some() {
+                i += 1;
+            } else if blocks[j].is_none() {
+                j -= 1;
+            } else {
+                blocks.swap(i, j);
+            }
+        }
+
+        DiskMap::from_blocks(&blocks)
+    }
+
+    // each extent moves at most once, in decreasing id order, and only into
+    // space that was free before compaction started
+    fn files_to_move(&self) -> Vec<Extent> {
+        let mut extents = self.extents.clone();
+        extents.sort_by_key(|e| Reverse((e.id, e.start)));
+        extents
+    }
+
+    fn compact_first_fit(&self) -> DiskMap {
+        // one min-heap of (start, len) per span size, the last one also
+        // holding every longer span
+        let max_len = self.extents.iter().map(|e| e.len).max().unwrap_or(0);
+        let mut heaps = vec![BinaryHeap::new(); max_len + 1];
+        let bucket = |len: usize| len.min(max_len);
+        for (start, len) in self.free_spans() {
+            heaps[bucket(len)].push(Reverse((start, len)));
+        }
+
+        let mut extents = self.files_to_move();
+        for extent in extents.iter_mut() {
+            let found = (extent.len..=max_len)
+                .filter_map(|size| heaps[size].peek().map(|Reverse(span)| (*span, size)))
+                .min();
+
+            if let Some(((start, len), size)) = found {
+                if start >= extent.start {
+                    continue;
+                }
+                heaps[size].pop();
+                extent.start = start;
+                if len > extent.len {
+                    let rest = (start + extent.len, len - extent.len);
+                    heaps[bucket(rest.1)].push(Reverse(rest));
+                }
+            }
+        }
+
+        DiskMap::from_extents(extents, self.size)
+    }
+
+    fn compact_best_fit(&self) -> DiskMap {
+        let mut frees = self.free_spans();
+        let mut extents = self.files_to_move();
+
+        for extent in extents.iter_mut() {
+            let free = frees
+                .iter_mut()
+                .take_while(|(start, _)| *start < extent.start)
+                .filter(|(_, len)| *len >= extent.len)
+                .min_by_key(|(start, len)| (*len, *start));
+
+            if let Some((start, len)) = free {
+                extent.start = *start;
+                *start += extent.len;
+                *len -= extent.len;
+            }
+        }
+
+        DiskMap::from_extents(extents, self.size)
+    }
+
+    fn defragment(&self) -> DiskMap {
+        // total length per id, in order of first appearance
+        let mut files: Vec<(usize, usize)> = vec![];
+        for extent in &self.extents {
+            match files.iter_mut().find(|(id, _)| *id == extent.id) {
+                Some((_, len)) => *len += extent.len,
+                None => files.push((extent.id, extent.len)),
+            }
+        }
 
-    let rev_blocks = nums
-        .iter()
-        .step_by(2)
-        .enumerate()
-        .rev();
+        let mut pos = 0;
+        let extents = files
+            .into_iter()
+            .map(|(id, len)| {
+                let extent = Extent {
+                    id,
+                    start: pos,
+                    len,
+                };
+                pos += len;
+                extent
+            })
+            .collect();
 
-    let mut frees: Vec<_> = nums.clone().into_iter().skip(1).step_by(2).collect();
+        DiskMap {
+            extents,
+            size: self.size,
+        }
+    }
 
-    // This is a vector of vectors, representing a bucket where blocks are inserted.
-    // in the end, the vector is flattened and the resulting vector is obtained.
-    let mut indexed = vec![vec![]; frees.len()];
+    fn from_blocks(blocks: &[Option<usize>]) -> DiskMap {
+        let extents = blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(start, id)| id.map(|id| Extent { id, start, len: 1 }))
+            .collect();
+        DiskMap::from_extents(extents, blocks.len())
+    }
 
-    // starting at the back, check if there is a free space for that block
-    // if there is, then move it there
-    // otherwise, we cannot move the block and the idx is frozen
-    for (rev_block, rev_size) in rev_blocks{
-        let my_idx = rev_block;
-        let free_idx = frees.iter().take(my_idx).position(|free| free >= rev_size);
-        if let Some(free_idx) = free_idx {
-            // found a free idx, let's occupy this space
-            let free = frees[free_idx];
-            frees[free_idx] = free - *rev_size;
+    // sorts the extents and joins neighbours of the same file
+    fn from_extents(mut extents: Vec<Extent>, size: usize) -> DiskMap {
+        extents.sort_by_key(|e| e.start);
 
-            // now we can move the block to the found idx
-            indexed[free_idx].push((rev_size, rev_block));
+        let mut merged: Vec<Extent> = Vec::with_capacity(extents.len());
+        for extent in extents {
+            match merged.last_mut() {
+                Some(last) if last.id == extent.id && last.start + last.len == extent.start => {
+                    last.len += extent.len;
+                }
+                _ => merged.push(extent),
+            }
+        }
 
-            // and make the spot free again
-            frees[my_idx - 1] += rev_size;
-            continue;
+        DiskMap {
+            extents: merged,
+            size,
         }
+    }
 
-        // Otherwise, we have found an element that cannot be moved
-        // in that case, we must move in to the front of the idx, as it takes priority
-        indexed[my_idx].insert(0, (rev_size, rev_block));
+    /// Writes the layout back in the dense digit format.
+    ///
+    /// The format has no room for ids, so reading it back numbers the files
+    /// by position. Runs longer than 9 blocks are split with empty runs.
+    pub fn to_dense(&self) -> String {
+        let mut out = String::new();
+        let mut pos = 0;
+        for extent in &self.extents {
+            push_run(&mut out, extent.start - pos, false);
+            push_run(&mut out, extent.len, true);
+            pos = extent.start + extent.len;
+        }
+        push_run(&mut out, self.size - pos, false);
+        out
     }
+}
 
-    // all we have to do is insert 0's in the places where we still have empty values
-    let mut result: u64 = 0;
-    let mut factor = 0;
-    for (num_free, items) in frees.iter().zip(indexed) {
-        for (size, item) in items {
-            let size = *size;
-            let sum = (size * item * (2 * factor + size - 1)) / 2;
-            result += sum as u64;
-            factor += size;
+// appends a run, padding with empty runs to keep files and free space
+// alternating
+fn push_run(out: &mut String, mut len: usize, file: bool) {
+    while len > 0 {
+        if out.len().is_multiple_of(2) != file {
+            out.push('0');
         }
-        factor += num_free;
+        let n = len.min(9);
+        out.push(char::from_digit(n as u32, 10).unwrap());
+        len -= n;
     }
+}
 
-    return result;
+/// The `00...111` block view, with ids written out in full.
+impl fmt::Display for DiskMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for block in self.blocks() {
+            match block {
+                Some(id) => write!(f, "{}", id)?,
+                None => write!(f, ".")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn part1(input: &str) -> u64 {
+    let disk = input.parse::<DiskMap>().expect("could not parse disk map");
+    disk.compact(Compaction::Blocks).checksum()
+}
+
+pub fn part2(input: &str) -> u64 {
+    let disk = input.parse::<DiskMap>().expect("could not parse disk map");
+    disk.compact(Compaction::FirstFit).checksum()
 }
@@ -1,4 +1,6 @@
 use day9::{part1, part2};
+#[cfg(test)]
+use day9::{Compaction, DiskMap};
 use std::fs;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -22,13 +24,13 @@ fn test_part_1() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part1(&contents);
     assert_eq!(num, 1928);
 
-    return Ok(());
+    Ok(())
 }
 
 #[test]
@@ -36,11 +38,63 @@ fn test_part_2() -> Result<(), Box<dyn std::error::Error>> {
     let file_path = "input/dummy.txt";
 
     // Read file contents
-    let contents = fs::read_to_string(&file_path)
+    let contents = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
 
     let num = part2(&contents);
     assert_eq!(num, 2858);
 
-    return Ok(());
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[test]
+fn test_disk_map() -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = "input/dummy.txt";
+
+    // Read file contents
+    let contents = fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+
+    let disk: DiskMap = contents.parse().map_err(|_| "invalid disk map")?;
+    assert_eq!(
+        disk.to_string(),
+        "00...111...2...333.44.5555.6666.777.888899"
+    );
+    assert_eq!(disk.to_dense(), contents.trim());
+
+    let blocks = disk.compact(Compaction::Blocks);
+    assert_eq!(
+        blocks.to_string(),
+        "0099811188827773336446555566.............."
+    );
+    assert_eq!(blocks.checksum(), 1928);
+
+    let first_fit = disk.compact(Compaction::FirstFit);
+    assert_eq!(
+        first_fit.to_string(),
+        "00992111777.44.333....5555.6666.....8888.."
+    );
+    assert_eq!(first_fit.checksum(), 2858);
+
+    // 2 takes the single free block, leaving the span of 3 for 1
+    let small: DiskMap = "13111".parse().map_err(|_| "invalid disk map")?;
+    assert_eq!(small.compact(Compaction::FirstFit).to_string(), "021....");
+    assert_eq!(small.compact(Compaction::BestFit).to_string(), "01...2.");
+
+    let defragmented = disk.compact(Compaction::Defragment);
+    assert_eq!(
+        defragmented.to_string(),
+        "0011123334455556666777888899.............."
+    );
+
+    // saving renumbers the files by position, but keeps the layout
+    let saved: DiskMap = first_fit
+        .to_dense()
+        .parse()
+        .map_err(|_| "invalid disk map")?;
+    assert_eq!(first_fit.to_dense(), "20201030312134414542");
+    assert_eq!(saved.size(), first_fit.size());
+    assert_eq!(saved.extents().len(), first_fit.extents().len());
+
+    Ok(())
+}